@@ -0,0 +1,326 @@
+use crate::CartridgeError;
+
+pub const HEADER_BASE: usize = 0x0100;
+pub const HEADER_END: usize = 0x0150;
+
+const LOGO_BASE: usize = 0x0104;
+const LOGO_END: usize = 0x0134;
+const TITLE_BASE: usize = 0x0134;
+const TITLE_END: usize = 0x0144;
+const CGB_FLAG: usize = 0x0143;
+const NEW_LICENSEE: usize = 0x0144;
+const SGB_FLAG: usize = 0x0146;
+const CARTRIDGE_TYPE: usize = 0x0147;
+const ROM_SIZE: usize = 0x0148;
+const RAM_SIZE: usize = 0x0149;
+const DESTINATION: usize = 0x014A;
+const OLD_LICENSEE: usize = 0x014B;
+const VERSION: usize = 0x014C;
+const HEADER_CHECKSUM: usize = 0x014D;
+const GLOBAL_CHECKSUM: usize = 0x014E;
+
+// Old licensee value telling that the new licensee code should be used instead.
+const USE_NEW_LICENSEE: u8 = 0x33;
+
+pub const ROM_BANK_SIZE: usize = 0x4000;
+pub const RAM_BANK_SIZE: usize = 0x2000;
+
+pub static NINTENDO_LOGO: [u8; LOGO_END - LOGO_BASE] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B,
+    0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E,
+    0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC,
+    0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CgbSupport {
+    None,
+    Enhanced,   // 0x80: works on DMG too
+    Only,       // 0xC0: CGB-only title
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mapper {
+    RomOnly,
+    Mbc1,
+    Mbc2,
+    Mmm01,
+    Mbc3,
+    Mbc5,
+    Mbc6,
+    Mbc7,
+    PocketCamera,
+    BandaiTama5,
+    HuC3,
+    HuC1,
+}
+
+// Decoded cartridge type byte (0x0147).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CartridgeType {
+    pub code: u8,
+    pub mapper: Mapper,
+    pub ram: bool,
+    pub battery: bool,
+    pub timer: bool,
+    pub rumble: bool,
+}
+
+impl CartridgeType {
+    pub fn from_code(code: u8) -> Result<CartridgeType, CartridgeError> {
+        // (mapper, ram, battery, timer, rumble)
+        let (mapper, ram, battery, timer, rumble) = match code {
+            0x00 => (Mapper::RomOnly, false, false, false, false),
+            0x01 => (Mapper::Mbc1, false, false, false, false),
+            0x02 => (Mapper::Mbc1, true, false, false, false),
+            0x03 => (Mapper::Mbc1, true, true, false, false),
+            0x05 => (Mapper::Mbc2, false, false, false, false),
+            0x06 => (Mapper::Mbc2, false, true, false, false),
+            0x08 => (Mapper::RomOnly, true, false, false, false),
+            0x09 => (Mapper::RomOnly, true, true, false, false),
+            0x0B => (Mapper::Mmm01, false, false, false, false),
+            0x0C => (Mapper::Mmm01, true, false, false, false),
+            0x0D => (Mapper::Mmm01, true, true, false, false),
+            0x0F => (Mapper::Mbc3, false, true, true, false),
+            0x10 => (Mapper::Mbc3, true, true, true, false),
+            0x11 => (Mapper::Mbc3, false, false, false, false),
+            0x12 => (Mapper::Mbc3, true, false, false, false),
+            0x13 => (Mapper::Mbc3, true, true, false, false),
+            0x19 => (Mapper::Mbc5, false, false, false, false),
+            0x1A => (Mapper::Mbc5, true, false, false, false),
+            0x1B => (Mapper::Mbc5, true, true, false, false),
+            0x1C => (Mapper::Mbc5, false, false, false, true),
+            0x1D => (Mapper::Mbc5, true, false, false, true),
+            0x1E => (Mapper::Mbc5, true, true, false, true),
+            0x20 => (Mapper::Mbc6, true, true, false, false),
+            0x22 => (Mapper::Mbc7, true, true, false, true),
+            0xFC => (Mapper::PocketCamera, true, false, false, false),
+            0xFD => (Mapper::BandaiTama5, false, false, false, false),
+            0xFE => (Mapper::HuC3, true, true, true, false),
+            0xFF => (Mapper::HuC1, true, true, false, false),
+            _ => return Err(CartridgeError::UnknownCartridgeType(code)),
+        };
+
+        Ok(CartridgeType { code, mapper, ram, battery, timer, rumble })
+    }
+
+    pub fn name(&self) -> String {
+        let mut name: String = match self.mapper {
+            Mapper::RomOnly => "ROM".to_string(),
+            Mapper::Mbc1 => "MBC1".to_string(),
+            Mapper::Mbc2 => "MBC2".to_string(),
+            Mapper::Mmm01 => "MMM01".to_string(),
+            Mapper::Mbc3 => "MBC3".to_string(),
+            Mapper::Mbc5 => "MBC5".to_string(),
+            Mapper::Mbc6 => "MBC6".to_string(),
+            Mapper::Mbc7 => "MBC7".to_string(),
+            Mapper::PocketCamera => "POCKET CAMERA".to_string(),
+            Mapper::BandaiTama5 => "BANDAI TAMA5".to_string(),
+            Mapper::HuC3 => "HuC3".to_string(),
+            Mapper::HuC1 => "HuC1".to_string(),
+        };
+        if self.timer { name.push_str("+TIMER"); }
+        if self.rumble { name.push_str("+RUMBLE"); }
+        if self.ram { name.push_str("+RAM"); }
+        if self.battery { name.push_str("+BATTERY"); }
+        name
+    }
+}
+
+// Parsed cartridge header (0x0100 - 0x014F).
+#[derive(Clone, Debug)]
+pub struct Header {
+    pub entry_point: [u8; 4],
+    pub logo_valid: bool,
+    pub title: String,
+    pub cgb: CgbSupport,
+    pub sgb: bool,
+    pub cartridge_type: CartridgeType,
+    pub rom_size_code: u8,
+    pub ram_size_code: u8,
+    pub destination: u8,
+    pub old_licensee: u8,
+    pub new_licensee: [u8; 2],
+    pub version: u8,
+    pub header_checksum: u8,
+    pub global_checksum: u16,
+}
+
+impl Header {
+    pub fn parse(rom: &[u8]) -> Result<Header, CartridgeError> {
+        if rom.len() < HEADER_END {
+            return Err(CartridgeError::Truncated { expected: HEADER_END, actual: rom.len() });
+        }
+
+        let cgb: CgbSupport = match rom[CGB_FLAG] {
+            0x80 => CgbSupport::Enhanced,
+            0xC0 => CgbSupport::Only,
+            _ => CgbSupport::None,
+        };
+
+        // On CGB-aware cartridges the last title byte is the CGB flag.
+        let title_end: usize = if cgb == CgbSupport::None { TITLE_END } else { CGB_FLAG };
+        let title: String = rom[TITLE_BASE..title_end].iter()
+            .take_while(|b| **b != 0)
+            .map(|b| if b.is_ascii_graphic() || *b == b' ' { *b as char } else { '?' })
+            .collect::<String>()
+            .trim_end()
+            .to_string();
+
+        let rom_size_code: u8 = rom[ROM_SIZE];
+        if rom_size_code > 0x08 {
+            return Err(CartridgeError::InvalidRomSize(rom_size_code));
+        }
+        let ram_size_code: u8 = rom[RAM_SIZE];
+        if ram_size_code > 0x05 {
+            return Err(CartridgeError::InvalidRamSize(ram_size_code));
+        }
+
+        Ok(Header {
+            entry_point: [rom[HEADER_BASE], rom[HEADER_BASE + 1], rom[HEADER_BASE + 2], rom[HEADER_BASE + 3]],
+            logo_valid: rom[LOGO_BASE..LOGO_END] == NINTENDO_LOGO,
+            title,
+            cgb,
+            sgb: rom[SGB_FLAG] == 0x03,
+            cartridge_type: CartridgeType::from_code(rom[CARTRIDGE_TYPE])?,
+            rom_size_code,
+            ram_size_code,
+            destination: rom[DESTINATION],
+            old_licensee: rom[OLD_LICENSEE],
+            new_licensee: [rom[NEW_LICENSEE], rom[NEW_LICENSEE + 1]],
+            version: rom[VERSION],
+            header_checksum: rom[HEADER_CHECKSUM],
+            global_checksum: ((rom[GLOBAL_CHECKSUM] as u16) << 8) | rom[GLOBAL_CHECKSUM + 1] as u16,
+        })
+    }
+
+    pub fn rom_size(&self) -> usize {
+        (2 * ROM_BANK_SIZE) << self.rom_size_code
+    }
+
+    pub fn rom_banks(&self) -> usize {
+        self.rom_size() / ROM_BANK_SIZE
+    }
+
+    pub fn ram_size(&self) -> usize {
+        match self.ram_size_code {
+            0x01 => 0x800, // unofficial 2 KiB, used by some homebrew
+            0x02 => RAM_BANK_SIZE,
+            0x03 => 4 * RAM_BANK_SIZE,
+            0x04 => 16 * RAM_BANK_SIZE,
+            0x05 => 8 * RAM_BANK_SIZE,
+            _ => 0,
+        }
+    }
+
+    pub fn licensee_code(&self) -> String {
+        if self.old_licensee == USE_NEW_LICENSEE {
+            self.new_licensee.iter().map(|b| *b as char).collect()
+        } else {
+            format!("{:02X}", self.old_licensee)
+        }
+    }
+
+    pub fn compute_header_checksum(rom: &[u8]) -> u8 {
+        rom[TITLE_BASE..HEADER_CHECKSUM].iter()
+            .fold(0u8, |acc, b| acc.wrapping_sub(*b).wrapping_sub(1))
+    }
+
+    pub fn compute_global_checksum(rom: &[u8]) -> u16 {
+        rom.iter().enumerate()
+            .filter(|(i, _)| *i != GLOBAL_CHECKSUM && *i != GLOBAL_CHECKSUM + 1)
+            .fold(0u16, |acc, (_, b)| acc.wrapping_add(*b as u16))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 32 KiB image with a valid logo and header checksum.
+    fn rom(cartridge_type: u8, rom_size: u8, ram_size: u8) -> Vec<u8> {
+        let mut rom: Vec<u8> = vec![0; 2 * ROM_BANK_SIZE];
+        rom[LOGO_BASE..LOGO_END].copy_from_slice(&NINTENDO_LOGO);
+        rom[TITLE_BASE..TITLE_BASE + 4].copy_from_slice(b"TEST");
+        rom[CARTRIDGE_TYPE] = cartridge_type;
+        rom[ROM_SIZE] = rom_size;
+        rom[RAM_SIZE] = ram_size;
+        rom[HEADER_CHECKSUM] = Header::compute_header_checksum(&rom);
+        rom
+    }
+
+    #[test]
+    fn logo_and_checksums() {
+        let mut rom: Vec<u8> = rom(0x00, 0x00, 0x00);
+        let header: Header = Header::parse(&rom).unwrap();
+        assert!(header.logo_valid);
+        assert_eq!(header.title, "TEST");
+        assert_eq!(header.header_checksum, Header::compute_header_checksum(&rom));
+
+        rom[LOGO_BASE + 7] ^= 0xFF;
+        assert!(!Header::parse(&rom).unwrap().logo_valid);
+
+        // x = x - byte - 1 over 0x0134 - 0x014C
+        let zeros: Vec<u8> = vec![0; HEADER_END];
+        assert_eq!(Header::compute_header_checksum(&zeros), 0xE7);
+
+        // Sum of every byte but the checksum itself
+        let mut image: Vec<u8> = vec![0; HEADER_END];
+        image[0x00] = 0xFF;
+        image[0x10] = 0x02;
+        image[GLOBAL_CHECKSUM] = 0x12;
+        image[GLOBAL_CHECKSUM + 1] = 0x34;
+        assert_eq!(Header::compute_global_checksum(&image), 0x0101);
+    }
+
+    #[test]
+    fn cgb_flag_shortens_the_title() {
+        let mut rom: Vec<u8> = rom(0x00, 0x00, 0x00);
+        rom[TITLE_BASE..TITLE_END].copy_from_slice(b"ABCDEFGHIJKLMNOP");
+        assert_eq!(Header::parse(&rom).unwrap().title, "ABCDEFGHIJKLMNOP");
+        rom[CGB_FLAG] = 0xC0;
+        let header: Header = Header::parse(&rom).unwrap();
+        assert_eq!(header.title, "ABCDEFGHIJKLMNO");
+        assert_eq!(header.cgb, CgbSupport::Only);
+    }
+
+    #[test]
+    fn rom_size_codes() {
+        for (code, banks) in [(0x00, 2), (0x01, 4), (0x05, 64), (0x08, 512)] {
+            let header: Header = Header::parse(&rom(0x00, code, 0x00)).unwrap();
+            assert_eq!(header.rom_banks(), banks, "{code}");
+            assert_eq!(header.rom_size(), banks * ROM_BANK_SIZE, "{code}");
+        }
+        assert_eq!(Header::parse(&rom(0x00, 0x09, 0x00)).unwrap_err(), CartridgeError::InvalidRomSize(0x09));
+    }
+
+    #[test]
+    fn ram_size_codes() {
+        for (code, size) in [(0x00, 0), (0x01, 0x800), (0x02, 0x2000), (0x03, 0x8000), (0x04, 0x20000), (0x05, 0x10000)] {
+            assert_eq!(Header::parse(&rom(0x03, 0x00, code)).unwrap().ram_size(), size, "{code}");
+        }
+        assert_eq!(Header::parse(&rom(0x03, 0x00, 0x06)).unwrap_err(), CartridgeError::InvalidRamSize(0x06));
+    }
+
+    #[test]
+    fn cartridge_types() {
+        let header: Header = Header::parse(&rom(0x10, 0x00, 0x03)).unwrap();
+        assert_eq!(header.cartridge_type.mapper, Mapper::Mbc3);
+        assert_eq!(header.cartridge_type.name(), "MBC3+TIMER+RAM+BATTERY");
+
+        for code in [0x04, 0x07, 0x0E, 0x14, 0x21, 0x23, 0xFB] {
+            assert_eq!(Header::parse(&rom(code, 0x00, 0x00)).unwrap_err(), CartridgeError::UnknownCartridgeType(code));
+        }
+    }
+
+    #[test]
+    fn truncated_images_are_rejected() {
+        let rom: Vec<u8> = rom(0x00, 0x00, 0x00);
+        assert_eq!(
+            Header::parse(&rom[..HEADER_END - 1]).unwrap_err(),
+            CartridgeError::Truncated { expected: HEADER_END, actual: HEADER_END - 1 },
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use crate::mbc::{ram_offset, rom_offset, CartridgeEvent, Mbc, ROM1_BASE};
+
+pub const IR_SELECT: u8 = 0x0E;
+// IR reads: 0xC1 when light is seen, 0xC0 otherwise
+pub const IR_BASE: u8 = 0xC0;
+
+// https://gbdev.io/pandocs/HuC1.html
+pub struct HuC1 {
+    ir_mode: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+    ir_input: bool,
+    ir_led: bool,
+    // Latest LED change not polled yet
+    event: Option<CartridgeEvent>,
+}
+
+impl HuC1 {
+    pub fn new() -> HuC1 {
+        HuC1 {
+            ir_mode: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            ir_input: false,
+            ir_led: false,
+            event: None,
+        }
+    }
+}
+
+impl Mbc for HuC1 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < ROM1_BASE {
+            rom[rom_offset(rom, 0, addr)]
+        } else {
+            rom[rom_offset(rom, self.rom_bank as usize, addr)]
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) {
+        match addr {
+            // There is no RAM enable, anything but IR select maps RAM
+            0x0000..0x2000 => self.ir_mode = val == IR_SELECT,
+            0x2000..0x4000 => {
+                self.rom_bank = val & 0x3F;
+                if self.rom_bank == 0 {
+                    self.rom_bank = 1;
+                }
+            },
+            0x4000..0x6000 => self.ram_bank = val & 0x03,
+            _ => (),
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if self.ir_mode {
+            return IR_BASE | self.ir_input as u8;
+        }
+        if ram.is_empty() {
+            return 0xFF;
+        }
+        ram[ram_offset(ram, self.ram_bank as usize, addr)]
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        if self.ir_mode {
+            let led: bool = val & 0x01 != 0;
+            if led != self.ir_led {
+                self.ir_led = led;
+                self.event = Some(CartridgeEvent::Infrared(led));
+            }
+            return false;
+        }
+        if ram.is_empty() {
+            return false;
+        }
+        ram[ram_offset(ram, self.ram_bank as usize, addr)] = val;
+        true
+    }
+
+    fn poll_event(&mut self) -> Option<CartridgeEvent> {
+        self.event.take()
+    }
+
+    fn set_ir_input(&mut self, light: bool) {
+        self.ir_input = light;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mbc::ERAM_BASE;
+    use crate::{RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+    fn rom(banks: usize) -> Vec<u8> {
+        (0..banks * ROM_BANK_SIZE).map(|i| (i / ROM_BANK_SIZE) as u8).collect()
+    }
+
+    #[test]
+    fn rom_and_ram_banking() {
+        let rom: Vec<u8> = rom(64);
+        let mut ram: Vec<u8> = vec![0; 4 * RAM_BANK_SIZE];
+        let mut mbc: HuC1 = HuC1::new();
+        mbc.write_rom(0x2000, 0x00);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 1);
+        mbc.write_rom(0x2000, 0xFF);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x3F);
+        assert_eq!(mbc.read_rom(&rom, 0x0000), 0);
+
+        // RAM needs no enabling
+        mbc.write_rom(0x4000, 0x03);
+        mbc.write_ram(&mut ram, ERAM_BASE + 1, 0x42);
+        assert_eq!(ram[3 * RAM_BANK_SIZE + 1], 0x42);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE + 1), 0x42);
+    }
+
+    #[test]
+    fn ir_mode_replaces_ram() {
+        let mut ram: Vec<u8> = vec![0; RAM_BANK_SIZE];
+        let mut mbc: HuC1 = HuC1::new();
+        mbc.write_rom(0x0000, IR_SELECT);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), IR_BASE);
+        mbc.set_ir_input(true);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), IR_BASE | 1);
+
+        for _ in 0..1000 {
+            mbc.write_ram(&mut ram, ERAM_BASE, 0x01);
+            mbc.write_ram(&mut ram, ERAM_BASE, 0x00);
+        }
+        assert_eq!(mbc.poll_event(), Some(CartridgeEvent::Infrared(false)));
+        assert_eq!(mbc.poll_event(), None);
+        assert!(ram.iter().all(|&b| b == 0));
+
+        mbc.write_rom(0x0000, 0x00);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), 0x00);
+    }
+}
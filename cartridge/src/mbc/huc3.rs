@@ -0,0 +1,279 @@
+use crate::{mbc::{huc1::{IR_BASE, IR_SELECT}, ram_offset, rom_offset, CartridgeEvent, Mbc, ROM1_BASE}, rtc::{CycleTimeSource, TimeSource, RTC_FREQUENCY}};
+
+const MODE_RAM_READ: u8 = 0x00;
+const MODE_RAM: u8 = 0x0A;
+const MODE_RTC_COMMAND: u8 = 0x0B;
+const MODE_RTC_RESPONSE: u8 = 0x0C;
+const MODE_RTC_SEMAPHORE: u8 = 0x0D;
+
+const RTC_CMD_READ: u8 = 0x1;
+const RTC_CMD_WRITE: u8 = 0x3;
+const RTC_CMD_ADDR_LOW: u8 = 0x4;
+const RTC_CMD_ADDR_HIGH: u8 = 0x5;
+const RTC_CMD_EXTENDED: u8 = 0x6;
+
+const RTC_EXT_LATCH: u8 = 0x0;
+const RTC_EXT_SET: u8 = 0x1;
+const RTC_EXT_STATUS: u8 = 0x2;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+const TICKS_PER_MINUTE: u64 = 60 * RTC_FREQUENCY;
+
+// HuC3 clock counts minutes of the day and days, both 12 bit, exchanged with
+// the game through a 256 nibble scratch memory.
+struct HuC3Rtc {
+    minutes: u16,
+    days: u16,
+    sub_minute: u64,
+    memory: [u8; 0x100],
+    address: u8,
+    command: u8,
+    response: u8,
+    source: Box<dyn TimeSource>,
+}
+
+impl HuC3Rtc {
+    fn new() -> HuC3Rtc {
+        HuC3Rtc {
+            minutes: 0,
+            days: 0,
+            sub_minute: 0,
+            memory: [0; 0x100],
+            address: 0,
+            command: 0,
+            response: 0,
+            source: Box::new(CycleTimeSource::new()),
+        }
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        self.sub_minute += self.source.advance(cycles);
+        while self.sub_minute >= TICKS_PER_MINUTE {
+            self.sub_minute -= TICKS_PER_MINUTE;
+            self.minutes += 1;
+            if self.minutes == MINUTES_PER_DAY {
+                self.minutes = 0;
+                self.days = (self.days + 1) & 0xFFF;
+            }
+        }
+    }
+
+    // Command byte: bits 4 - 6 command, bits 0 - 3 argument
+    fn execute(&mut self, val: u8) {
+        let cmd: u8 = (val >> 4) & 0x07;
+        let arg: u8 = val & 0x0F;
+        self.command = cmd;
+        match cmd {
+            RTC_CMD_READ => {
+                self.response = self.memory[self.address as usize];
+                self.address = self.address.wrapping_add(1);
+            },
+            RTC_CMD_WRITE => {
+                self.memory[self.address as usize] = arg;
+                self.address = self.address.wrapping_add(1);
+            },
+            RTC_CMD_ADDR_LOW => self.address = (self.address & 0xF0) | arg,
+            RTC_CMD_ADDR_HIGH => self.address = (self.address & 0x0F) | (arg << 4),
+            RTC_CMD_EXTENDED => match arg {
+                RTC_EXT_LATCH => {
+                    for i in 0..3 {
+                        self.memory[i] = ((self.minutes >> (4 * i)) & 0x0F) as u8;
+                        self.memory[3 + i] = ((self.days >> (4 * i)) & 0x0F) as u8;
+                    }
+                },
+                RTC_EXT_SET => {
+                    let mut minutes: u16 = 0;
+                    let mut days: u16 = 0;
+                    for i in 0..3 {
+                        minutes |= (self.memory[i] as u16) << (4 * i);
+                        days |= (self.memory[3 + i] as u16) << (4 * i);
+                    }
+                    self.minutes = minutes % MINUTES_PER_DAY;
+                    self.days = days;
+                    self.sub_minute = 0;
+                },
+                RTC_EXT_STATUS => self.response = 0x01,
+                _ => (),    // tone generator and friends
+            },
+            _ => (),
+        }
+    }
+}
+
+// https://gbdev.io/pandocs/HuC3.html
+pub struct HuC3 {
+    mode: u8,
+    rom_bank: u8,
+    ram_bank: u8,
+    ir_input: bool,
+    ir_led: bool,
+    rtc: HuC3Rtc,
+    // Latest LED change not polled yet
+    event: Option<CartridgeEvent>,
+}
+
+impl HuC3 {
+    pub fn new() -> HuC3 {
+        HuC3 {
+            mode: MODE_RAM_READ,
+            rom_bank: 1,
+            ram_bank: 0,
+            ir_input: false,
+            ir_led: false,
+            rtc: HuC3Rtc::new(),
+            event: None,
+        }
+    }
+}
+
+impl Mbc for HuC3 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < ROM1_BASE {
+            rom[rom_offset(rom, 0, addr)]
+        } else {
+            rom[rom_offset(rom, self.rom_bank as usize, addr)]
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x0000..0x2000 => self.mode = val & 0x0F,
+            0x2000..0x4000 => self.rom_bank = val & 0x7F,
+            0x4000..0x6000 => self.ram_bank = val & 0x03,
+            _ => (),
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        match self.mode {
+            MODE_RTC_RESPONSE => 0x80 | (self.rtc.command << 4) | self.rtc.response,
+            // Commands complete instantly, the semaphore always reads as ready
+            MODE_RTC_SEMAPHORE => 0x01,
+            IR_SELECT => IR_BASE | self.ir_input as u8,
+            MODE_RTC_COMMAND => 0xFF,
+            _ => {
+                if ram.is_empty() {
+                    return 0xFF;
+                }
+                ram[ram_offset(ram, self.ram_bank as usize, addr)]
+            },
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        match self.mode {
+            MODE_RAM if !ram.is_empty() => {
+                ram[ram_offset(ram, self.ram_bank as usize, addr)] = val;
+                return true;
+            },
+            MODE_RTC_COMMAND => self.rtc.execute(val),
+            IR_SELECT => {
+                let led: bool = val & 0x01 != 0;
+                if led != self.ir_led {
+                    self.ir_led = led;
+                    self.event = Some(CartridgeEvent::Infrared(led));
+                }
+            },
+            _ => (),
+        }
+        false
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        self.rtc.tick(cycles);
+    }
+
+    fn set_time_source(&mut self, source: Box<dyn TimeSource>) {
+        self.rtc.source = source;
+    }
+
+    fn poll_event(&mut self) -> Option<CartridgeEvent> {
+        self.event.take()
+    }
+
+    fn set_ir_input(&mut self, light: bool) {
+        self.ir_input = light;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mbc::ERAM_BASE;
+    use crate::{RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+    const CYCLES_PER_MINUTE: u32 = 60 * 4_194_304;
+
+    fn command(mbc: &mut HuC3, ram: &mut [u8], val: u8) {
+        mbc.write_rom(0x0000, MODE_RTC_COMMAND);
+        mbc.write_ram(ram, ERAM_BASE, val);
+    }
+
+    fn response(mbc: &mut HuC3, ram: &[u8]) -> u8 {
+        mbc.write_rom(0x0000, MODE_RTC_RESPONSE);
+        mbc.read_ram(ram, ERAM_BASE)
+    }
+
+    #[test]
+    fn rom_and_ram_banking() {
+        let rom: Vec<u8> = (0..128 * ROM_BANK_SIZE).map(|i| (i / ROM_BANK_SIZE) as u8).collect();
+        let mut ram: Vec<u8> = vec![0; 4 * RAM_BANK_SIZE];
+        let mut mbc: HuC3 = HuC3::new();
+        mbc.write_rom(0x2000, 0xFF);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x7F);
+        // Unlike MBC1, bank 0 can be mapped twice
+        mbc.write_rom(0x2000, 0x00);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0);
+
+        mbc.write_rom(0x4000, 0x02);
+        mbc.write_ram(&mut ram, ERAM_BASE, 0x42);
+        assert_eq!(ram[2 * RAM_BANK_SIZE], 0x00);
+        mbc.write_rom(0x0000, MODE_RAM);
+        mbc.write_ram(&mut ram, ERAM_BASE, 0x42);
+        assert_eq!(ram[2 * RAM_BANK_SIZE], 0x42);
+        mbc.write_rom(0x0000, MODE_RAM_READ);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), 0x42);
+    }
+
+    #[test]
+    fn clock_is_set_and_latched_through_commands() {
+        let mut ram: Vec<u8> = vec![0; RAM_BANK_SIZE];
+        let mut mbc: HuC3 = HuC3::new();
+        // 23:59 on day 2
+        command(&mut mbc, &mut ram, 0x40);
+        command(&mut mbc, &mut ram, 0x50);
+        for nibble in [0xF, 0x9, 0x5, 0x2, 0x0, 0x0] {
+            command(&mut mbc, &mut ram, 0x30 | nibble);
+        }
+        command(&mut mbc, &mut ram, 0x60 | RTC_EXT_SET);
+        mbc.write_rom(0x0000, MODE_RTC_SEMAPHORE);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), 0x01);
+
+        mbc.tick(CYCLES_PER_MINUTE);
+        command(&mut mbc, &mut ram, 0x60 | RTC_EXT_LATCH);
+        command(&mut mbc, &mut ram, 0x40);
+        let mut nibbles: Vec<u8> = Vec::new();
+        for _ in 0..6 {
+            command(&mut mbc, &mut ram, 0x10);
+            let val: u8 = response(&mut mbc, &ram);
+            assert_eq!(val & 0xF0, 0x80 | (RTC_CMD_READ << 4));
+            nibbles.push(val & 0x0F);
+        }
+        assert_eq!(nibbles, [0, 0, 0, 3, 0, 0]);
+    }
+
+    #[test]
+    fn only_the_latest_led_change_is_kept() {
+        let mut ram: Vec<u8> = vec![0; RAM_BANK_SIZE];
+        let mut mbc: HuC3 = HuC3::new();
+        mbc.write_rom(0x0000, IR_SELECT);
+        mbc.set_ir_input(true);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), IR_BASE | 1);
+        for _ in 0..1000 {
+            mbc.write_ram(&mut ram, ERAM_BASE, 0x00);
+            mbc.write_ram(&mut ram, ERAM_BASE, 0x01);
+        }
+        assert_eq!(mbc.poll_event(), Some(CartridgeEvent::Infrared(true)));
+        assert_eq!(mbc.poll_event(), None);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::mbc::{ram_offset, rom_offset, Mbc, ROM1_BASE};
+
+// https://gbdev.io/pandocs/MBC1.html
+pub struct Mbc1 {
+    ram_enabled: bool,
+    bank1: u8,      // 5 bit ROM bank register (0x2000 - 0x3FFF)
+    bank2: u8,      // 2 bit upper ROM bank / RAM bank register (0x4000 - 0x5FFF)
+    mode: u8,       // banking mode select (0x6000 - 0x7FFF)
+    multicart: bool,
+}
+
+impl Mbc1 {
+    pub fn new(multicart: bool) -> Mbc1 {
+        Mbc1 {
+            ram_enabled: false,
+            bank1: 1,
+            bank2: 0,
+            mode: 0,
+            multicart,
+        }
+    }
+
+    // On MBC1M the BANK2 bits are wired one position lower and BANK1 bit 4 is
+    // not connected, so each game sees 16 banks.
+    fn bank2_shift(&self) -> u8 {
+        if self.multicart { 4 } else { 5 }
+    }
+
+    fn low_bank(&self) -> usize {
+        match self.mode {
+            0 => 0,
+            _ => (self.bank2 << self.bank2_shift()) as usize,
+        }
+    }
+
+    fn high_bank(&self) -> usize {
+        let bank1: u8 = if self.multicart { self.bank1 & 0x0F } else { self.bank1 };
+        ((self.bank2 << self.bank2_shift()) | bank1) as usize
+    }
+
+    fn ram_bank(&self) -> usize {
+        match self.mode {
+            0 => 0,
+            _ => self.bank2 as usize,
+        }
+    }
+}
+
+impl Mbc for Mbc1 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < ROM1_BASE {
+            rom[rom_offset(rom, self.low_bank(), addr)]
+        } else {
+            rom[rom_offset(rom, self.high_bank(), addr)]
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x0000..0x2000 => self.ram_enabled = val & 0x0F == 0x0A,
+            0x2000..0x4000 => {
+                // The zero check looks at all 5 bits, hence banks 0x20/0x40/0x60
+                // can't be mapped in the 0x4000 window.
+                self.bank1 = val & 0x1F;
+                if self.bank1 == 0 {
+                    self.bank1 = 1;
+                }
+            },
+            0x4000..0x6000 => self.bank2 = val & 0x03,
+            _ => self.mode = val & 0x01,
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled || ram.is_empty() {
+            return 0xFF;
+        }
+        ram[ram_offset(ram, self.ram_bank(), addr)]
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        if !self.ram_enabled || ram.is_empty() {
+            return false;
+        }
+        ram[ram_offset(ram, self.ram_bank(), addr)] = val;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mbc::{is_mbc1_multicart, ERAM_BASE};
+    use crate::{NINTENDO_LOGO, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+    // Every byte of a bank holds its number.
+    fn rom(banks: usize) -> Vec<u8> {
+        (0..banks * ROM_BANK_SIZE).map(|i| (i / ROM_BANK_SIZE) as u8).collect()
+    }
+
+    #[test]
+    fn bank_0_maps_to_bank_1() {
+        let rom: Vec<u8> = rom(64);
+        let mut mbc: Mbc1 = Mbc1::new(false);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 1);
+        mbc.write_rom(0x2000, 0x00);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 1);
+        mbc.write_rom(0x2000, 0x1F);
+        assert_eq!(mbc.read_rom(&rom, 0x7FFF), 0x1F);
+
+        // Only the 5 bits of BANK1 are checked, 0x20 reads 0x21
+        mbc.write_rom(0x2000, 0x00);
+        mbc.write_rom(0x4000, 0x01);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x21);
+        // Banks above the ROM size wrap around
+        mbc.write_rom(0x4000, 0x03);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x21);
+    }
+
+    #[test]
+    fn mode_1_banks_the_low_window_and_ram() {
+        let rom: Vec<u8> = rom(64);
+        let mut ram: Vec<u8> = vec![0; 4 * RAM_BANK_SIZE];
+        let mut mbc: Mbc1 = Mbc1::new(false);
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_rom(0x4000, 0x01);
+        assert_eq!(mbc.read_rom(&rom, 0x0000), 0);
+
+        mbc.write_rom(0x6000, 0x01);
+        assert_eq!(mbc.read_rom(&rom, 0x0000), 0x20);
+        mbc.write_rom(0x4000, 0x02);
+        mbc.write_ram(&mut ram, ERAM_BASE, 0x42);
+        assert_eq!(ram[2 * RAM_BANK_SIZE], 0x42);
+
+        // Mode 0 pins RAM bank 0
+        mbc.write_rom(0x6000, 0x00);
+        assert_eq!(mbc.read_rom(&rom, 0x0000), 0);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), 0x00);
+        mbc.write_rom(0x6000, 0x01);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), 0x42);
+    }
+
+    #[test]
+    fn ram_needs_enabling() {
+        let mut ram: Vec<u8> = vec![0; RAM_BANK_SIZE];
+        let mut mbc: Mbc1 = Mbc1::new(false);
+        mbc.write_ram(&mut ram, ERAM_BASE, 0x12);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), 0xFF);
+        assert_eq!(ram[0], 0x00);
+
+        // Any value with 0xA in the low nibble
+        mbc.write_rom(0x1FFF, 0xFA);
+        mbc.write_ram(&mut ram, ERAM_BASE, 0x12);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), 0x12);
+        mbc.write_rom(0x0000, 0x00);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), 0xFF);
+    }
+
+    #[test]
+    fn multicart_wiring() {
+        let rom: Vec<u8> = rom(64);
+        let mut mbc: Mbc1 = Mbc1::new(true);
+        // BANK1 bit 4 is not connected, BANK2 lands on bits 4 - 5
+        mbc.write_rom(0x2000, 0x12);
+        mbc.write_rom(0x4000, 0x01);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x12);
+        mbc.write_rom(0x4000, 0x03);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x32);
+
+        // Mode 1 switches games in the low window
+        mbc.write_rom(0x6000, 0x01);
+        assert_eq!(mbc.read_rom(&rom, 0x0000), 0x30);
+    }
+
+    #[test]
+    fn multicarts_are_told_by_the_repeated_logo() {
+        let mut rom: Vec<u8> = vec![0; 64 * ROM_BANK_SIZE];
+        assert!(!is_mbc1_multicart(&rom));
+        let logo: usize = 16 * ROM_BANK_SIZE + 0x104;
+        rom[logo..logo + NINTENDO_LOGO.len()].copy_from_slice(&NINTENDO_LOGO);
+        assert!(is_mbc1_multicart(&rom));
+        // Only 8 Mbit boards
+        rom.truncate(32 * ROM_BANK_SIZE);
+        assert!(!is_mbc1_multicart(&rom));
+    }
+}
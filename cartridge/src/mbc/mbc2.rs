@@ -0,0 +1,109 @@
+use crate::mbc::{rom_offset, Mbc, ROM1_BASE};
+
+pub const MBC2_RAM_SIZE: usize = 0x200;
+
+// https://gbdev.io/pandocs/MBC2.html
+pub struct Mbc2 {
+    ram_enabled: bool,
+    rom_bank: u8,
+}
+
+impl Mbc2 {
+    pub fn new() -> Mbc2 {
+        Mbc2 {
+            ram_enabled: false,
+            rom_bank: 1,
+        }
+    }
+}
+
+impl Mbc for Mbc2 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < ROM1_BASE {
+            rom[rom_offset(rom, 0, addr)]
+        } else {
+            rom[rom_offset(rom, self.rom_bank as usize, addr)]
+        }
+    }
+
+    // Both registers live in 0x0000 - 0x3FFF, address bit 8 picks which one.
+    fn write_rom(&mut self, addr: u16, val: u8) {
+        if addr >= ROM1_BASE {
+            return;
+        }
+
+        if addr & 0x100 == 0 {
+            self.ram_enabled = val & 0x0F == 0x0A;
+        } else {
+            self.rom_bank = val & 0x0F;
+            if self.rom_bank == 0 {
+                self.rom_bank = 1;
+            }
+        }
+    }
+
+    // Built-in 512 x 4 bit RAM, echoed through the whole 0xA000 - 0xBFFF window.
+    // Upper nibble is not connected and reads back as 1s.
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+        0xF0 | ram[addr as usize & (MBC2_RAM_SIZE - 1)]
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        if !self.ram_enabled {
+            return false;
+        }
+        ram[addr as usize & (MBC2_RAM_SIZE - 1)] = val & 0x0F;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mbc::ERAM_BASE;
+    use crate::ROM_BANK_SIZE;
+
+    #[test]
+    fn address_bit_8_selects_the_register() {
+        let rom: Vec<u8> = (0..16 * ROM_BANK_SIZE).map(|i| (i / ROM_BANK_SIZE) as u8).collect();
+        let mut mbc: Mbc2 = Mbc2::new();
+        mbc.write_rom(0x2100, 0x03);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 3);
+        // Only the low 4 bits, 0 maps to 1
+        mbc.write_rom(0x3FFF, 0xF5);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 5);
+        mbc.write_rom(0x0100, 0x00);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 1);
+        assert_eq!(mbc.read_rom(&rom, 0x0000), 0);
+
+        // Bit 8 clear is RAM enable, whatever the address
+        mbc.write_rom(0x2000, 0x0A);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 1);
+        assert!(mbc.ram_enabled);
+        // Above 0x3FFF nothing happens
+        mbc.write_rom(0x4100, 0x02);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 1);
+    }
+
+    #[test]
+    fn ram_is_4_bits_wide_and_mirrored() {
+        let mut ram: Vec<u8> = vec![0; MBC2_RAM_SIZE];
+        let mut mbc: Mbc2 = Mbc2::new();
+        mbc.write_ram(&mut ram, ERAM_BASE, 0xAB);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), 0xFF);
+        assert_eq!(ram[0], 0x00);
+
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_ram(&mut ram, ERAM_BASE, 0xAB);
+        assert_eq!(ram[0], 0x0B);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), 0xFB);
+        // 512 half-bytes repeat through 0xA000 - 0xBFFF
+        assert_eq!(mbc.read_ram(&ram, 0xA200), 0xFB);
+        assert_eq!(mbc.read_ram(&ram, 0xBE00), 0xFB);
+        mbc.write_ram(&mut ram, 0xBFFF, 0x07);
+        assert_eq!(mbc.read_ram(&ram, 0xA1FF), 0xF7);
+    }
+}
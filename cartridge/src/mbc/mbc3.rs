@@ -0,0 +1,118 @@
+use crate::{mbc::{ram_offset, rom_offset, Mbc, ROM1_BASE}, rtc::{Rtc, TimeSource}};
+
+const RTC_SELECT_BASE: u8 = 0x08;
+const RTC_SELECT_END: u8 = 0x0C;
+
+// https://gbdev.io/pandocs/MBC3.html
+pub struct Mbc3 {
+    ram_enabled: bool,
+    rom_bank: u8,
+    // 0x00 - 0x07 selects a RAM bank, 0x08 - 0x0C an RTC register
+    ram_select: u8,
+    latch_armed: bool,
+    rtc: Option<Rtc>,
+}
+
+impl Mbc3 {
+    pub fn new(has_rtc: bool) -> Mbc3 {
+        Mbc3 {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_select: 0,
+            latch_armed: false,
+            rtc: if has_rtc { Some(Rtc::new()) } else { None },
+        }
+    }
+
+    fn selected_rtc_register(&self) -> Option<u8> {
+        if (RTC_SELECT_BASE..=RTC_SELECT_END).contains(&self.ram_select) && self.rtc.is_some() {
+            Some(self.ram_select - RTC_SELECT_BASE)
+        } else {
+            None
+        }
+    }
+}
+
+impl Mbc for Mbc3 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < ROM1_BASE {
+            rom[rom_offset(rom, 0, addr)]
+        } else {
+            rom[rom_offset(rom, self.rom_bank as usize, addr)]
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x0000..0x2000 => self.ram_enabled = val & 0x0F == 0x0A,
+            0x2000..0x4000 => {
+                // MBC30 boards decode all 8 bits, the modulo in rom_offset takes
+                // care of the 7 bit MBC3.
+                self.rom_bank = val;
+                if self.rom_bank == 0 {
+                    self.rom_bank = 1;
+                }
+            },
+            0x4000..0x6000 => self.ram_select = val & 0x0F,
+            _ => {
+                // Latching happens on a 0x00 -> 0x01 write sequence
+                if self.latch_armed && val == 0x01
+                    && let Some(rtc) = self.rtc.as_mut() {
+                    rtc.latch();
+                }
+                self.latch_armed = val == 0x00;
+            },
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+
+        if let Some(idx) = self.selected_rtc_register() {
+            return self.rtc.as_ref().unwrap().read(idx);
+        }
+        if self.ram_select >= RTC_SELECT_BASE || ram.is_empty() {
+            return 0xFF;
+        }
+        ram[ram_offset(ram, self.ram_select as usize, addr)]
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        if !self.ram_enabled {
+            return false;
+        }
+
+        // The clock registers are saved along with the RAM
+        if let Some(idx) = self.selected_rtc_register() {
+            self.rtc.as_mut().unwrap().write(idx, val);
+            return true;
+        }
+        if self.ram_select >= RTC_SELECT_BASE || ram.is_empty() {
+            return false;
+        }
+        ram[ram_offset(ram, self.ram_select as usize, addr)] = val;
+        true
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        if let Some(rtc) = self.rtc.as_mut() {
+            rtc.tick(cycles);
+        }
+    }
+
+    fn set_time_source(&mut self, source: Box<dyn TimeSource>) {
+        if let Some(rtc) = self.rtc.as_mut() {
+            rtc.set_time_source(source);
+        }
+    }
+
+    fn rtc(&self) -> Option<&Rtc> {
+        self.rtc.as_ref()
+    }
+
+    fn rtc_mut(&mut self) -> Option<&mut Rtc> {
+        self.rtc.as_mut()
+    }
+}
@@ -0,0 +1,108 @@
+use crate::mbc::{ram_offset, rom_offset, CartridgeEvent, Mbc, ROM1_BASE};
+
+// Bit 3 of the RAM bank register drives the motor on rumble boards
+const RUMBLE_MOTOR: u8 = 0x08;
+
+// https://gbdev.io/pandocs/MBC5.html
+pub struct Mbc5 {
+    ram_enabled: bool,
+    rom_bank: u16,      // 9 bit, bank 0 is allowed in the 0x4000 window
+    ram_bank: u8,
+    rumble: bool,
+    motor_on: bool,
+    // Latest motor change not polled yet, the host only cares where it ends up
+    event: Option<CartridgeEvent>,
+}
+
+impl Mbc5 {
+    pub fn new(rumble: bool) -> Mbc5 {
+        Mbc5 {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            rumble,
+            motor_on: false,
+            event: None,
+        }
+    }
+}
+
+impl Mbc for Mbc5 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < ROM1_BASE {
+            rom[rom_offset(rom, 0, addr)]
+        } else {
+            rom[rom_offset(rom, self.rom_bank as usize, addr)]
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x0000..0x2000 => self.ram_enabled = val & 0x0F == 0x0A,
+            0x2000..0x3000 => self.rom_bank = (self.rom_bank & 0x100) | val as u16,
+            0x3000..0x4000 => self.rom_bank = (self.rom_bank & 0xFF) | (((val & 0x01) as u16) << 8),
+            0x4000..0x6000 => {
+                if self.rumble {
+                    let motor_on: bool = val & RUMBLE_MOTOR != 0;
+                    if motor_on != self.motor_on {
+                        self.motor_on = motor_on;
+                        self.event = Some(CartridgeEvent::Rumble(motor_on));
+                    }
+                    self.ram_bank = val & 0x07;
+                } else {
+                    self.ram_bank = val & 0x0F;
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled || ram.is_empty() {
+            return 0xFF;
+        }
+        ram[ram_offset(ram, self.ram_bank as usize, addr)]
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        if !self.ram_enabled || ram.is_empty() {
+            return false;
+        }
+        ram[ram_offset(ram, self.ram_bank as usize, addr)] = val;
+        true
+    }
+
+    fn poll_event(&mut self) -> Option<CartridgeEvent> {
+        self.event.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_latest_motor_change_is_kept() {
+        let mut mbc: Mbc5 = Mbc5::new(true);
+        assert_eq!(mbc.poll_event(), None);
+        for _ in 0..1000 {
+            mbc.write_rom(0x4000, RUMBLE_MOTOR);
+            mbc.write_rom(0x4000, 0);
+        }
+        mbc.write_rom(0x4000, RUMBLE_MOTOR | 0x03);
+        assert_eq!(mbc.poll_event(), Some(CartridgeEvent::Rumble(true)));
+        assert_eq!(mbc.poll_event(), None);
+        // Same state again is not a change
+        mbc.write_rom(0x4000, RUMBLE_MOTOR);
+        assert_eq!(mbc.poll_event(), None);
+        assert_eq!(mbc.ram_bank, 0);
+    }
+
+    #[test]
+    fn boards_without_a_motor_use_bit_3_for_ram() {
+        let mut mbc: Mbc5 = Mbc5::new(false);
+        mbc.write_rom(0x4000, 0x0F);
+        assert_eq!(mbc.ram_bank, 0x0F);
+        assert_eq!(mbc.poll_event(), None);
+    }
+}
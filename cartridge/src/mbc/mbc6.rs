@@ -0,0 +1,125 @@
+use crate::mbc::{Mbc, ERAM_BASE};
+
+const MBC6_ROM_BANK_SIZE: usize = 0x2000;
+const MBC6_RAM_BANK_SIZE: usize = 0x1000;
+
+// https://gbdev.io/pandocs/MBC6.html
+// Only ROM and RAM banking is modelled. The 1 MiB flash chip is not, selecting
+// it in either ROM window reads open bus.
+pub struct Mbc6 {
+    ram_enabled: bool,
+    ram_bank_a: u8,     // 0xA000 - 0xAFFF
+    ram_bank_b: u8,     // 0xB000 - 0xBFFF
+    rom_bank_a: u8,     // 0x4000 - 0x5FFF
+    rom_bank_b: u8,     // 0x6000 - 0x7FFF
+    flash_a: bool,
+    flash_b: bool,
+}
+
+impl Mbc6 {
+    pub fn new() -> Mbc6 {
+        Mbc6 {
+            ram_enabled: false,
+            ram_bank_a: 0,
+            ram_bank_b: 0,
+            rom_bank_a: 0,
+            rom_bank_b: 0,
+            flash_a: false,
+            flash_b: false,
+        }
+    }
+
+    fn ram_index(&self, ram: &[u8], addr: u16) -> usize {
+        let bank: u8 = if addr < ERAM_BASE + MBC6_RAM_BANK_SIZE as u16 { self.ram_bank_a } else { self.ram_bank_b };
+        (bank as usize * MBC6_RAM_BANK_SIZE + (addr as usize & (MBC6_RAM_BANK_SIZE - 1))) % ram.len()
+    }
+}
+
+impl Mbc for Mbc6 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        let (bank, flash): (u8, bool) = match addr {
+            0x0000..0x4000 => return rom[addr as usize],
+            0x4000..0x6000 => (self.rom_bank_a, self.flash_a),
+            _ => (self.rom_bank_b, self.flash_b),
+        };
+        if flash {
+            return 0xFF;
+        }
+        rom[(bank as usize * MBC6_ROM_BANK_SIZE + (addr as usize & (MBC6_ROM_BANK_SIZE - 1))) % rom.len()]
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x0000..0x0400 => self.ram_enabled = val & 0x0F == 0x0A,
+            0x0400..0x0800 => self.ram_bank_a = val & 0x07,
+            0x0800..0x0C00 => self.ram_bank_b = val & 0x07,
+            0x2000..0x2800 => self.rom_bank_a = val & 0x7F,
+            0x2800..0x3000 => self.flash_a = val == 0x08,
+            0x3000..0x3800 => self.rom_bank_b = val & 0x7F,
+            0x3800..0x4000 => self.flash_b = val == 0x08,
+            _ => (),    // flash control
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled || ram.is_empty() {
+            return 0xFF;
+        }
+        ram[self.ram_index(ram, addr)]
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        if !self.ram_enabled || ram.is_empty() {
+            return false;
+        }
+        let idx: usize = self.ram_index(ram, addr);
+        ram[idx] = val;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_8k_rom_windows() {
+        let rom: Vec<u8> = (0..64 * MBC6_ROM_BANK_SIZE).map(|i| (i / MBC6_ROM_BANK_SIZE) as u8).collect();
+        let mut mbc: Mbc6 = Mbc6::new();
+        assert_eq!(mbc.read_rom(&rom, 0x3FFF), 1);
+        mbc.write_rom(0x2000, 0x05);
+        mbc.write_rom(0x3000, 0x2A);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x05);
+        assert_eq!(mbc.read_rom(&rom, 0x5FFF), 0x05);
+        assert_eq!(mbc.read_rom(&rom, 0x6000), 0x2A);
+        // Banks above the ROM size wrap around
+        mbc.write_rom(0x2000, 0x45);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x05);
+
+        // Flash is not there
+        mbc.write_rom(0x2800, 0x08);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0xFF);
+        assert_eq!(mbc.read_rom(&rom, 0x6000), 0x2A);
+        mbc.write_rom(0x2800, 0x00);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x05);
+    }
+
+    #[test]
+    fn two_4k_ram_windows() {
+        let mut ram: Vec<u8> = vec![0; 8 * MBC6_RAM_BANK_SIZE];
+        let mut mbc: Mbc6 = Mbc6::new();
+        mbc.write_rom(0x0400, 0x01);
+        mbc.write_rom(0x0800, 0x06);
+        mbc.write_ram(&mut ram, 0xA000, 0x11);
+        assert_eq!(mbc.read_ram(&ram, 0xA000), 0xFF);
+        assert!(ram.iter().all(|&b| b == 0));
+
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_ram(&mut ram, 0xA000, 0x11);
+        mbc.write_ram(&mut ram, 0xBFFF, 0x22);
+        assert_eq!(ram[MBC6_RAM_BANK_SIZE], 0x11);
+        assert_eq!(ram[7 * MBC6_RAM_BANK_SIZE - 1], 0x22);
+        assert_eq!(mbc.read_ram(&ram, 0xA000), 0x11);
+        assert_eq!(mbc.read_ram(&ram, 0xBFFF), 0x22);
+    }
+}
@@ -0,0 +1,408 @@
+use crate::mbc::{rom_offset, Mbc, ROM1_BASE};
+
+// 93LC56 EEPROM, 128 16 bit words
+pub const MBC7_EEPROM_SIZE: usize = 0x100;
+
+const ACCEL_CENTER: u16 = 0x81D0;
+const ACCEL_ERASED: u16 = 0x8000;
+// Roughly one g of tilt in accelerometer units
+const ACCEL_ONE_G: f32 = 112.0;
+
+const EEPROM_CS: u8 = 0x80;
+const EEPROM_CLK: u8 = 0x40;
+const EEPROM_DI: u8 = 0x02;
+const EEPROM_DO: u8 = 0x01;
+
+// 2 bit opcode + 8 bit address, following the start bit
+const EEPROM_COMMAND_BITS: u8 = 10;
+
+enum EepromState {
+    Idle,
+    Command,
+    Read { word: u16, bits: u8 },
+    Write { addr: u8, data: u16, bits: u8 },
+    WriteAll { data: u16, bits: u8 },
+}
+
+struct Eeprom {
+    cs: bool,
+    clk: bool,
+    data_out: bool,
+    write_enabled: bool,
+    shift: u16,
+    bits: u8,
+    state: EepromState,
+}
+
+impl Eeprom {
+    fn new() -> Eeprom {
+        Eeprom {
+            cs: false,
+            clk: false,
+            data_out: true,
+            write_enabled: false,
+            shift: 0,
+            bits: 0,
+            state: EepromState::Idle,
+        }
+    }
+
+    fn read_word(ram: &[u8], addr: u8) -> u16 {
+        let idx: usize = (addr as usize * 2) % MBC7_EEPROM_SIZE;
+        ram[idx] as u16 | ((ram[idx + 1] as u16) << 8)
+    }
+
+    fn write_word(ram: &mut [u8], addr: u8, val: u16) {
+        let idx: usize = (addr as usize * 2) % MBC7_EEPROM_SIZE;
+        ram[idx] = val as u8;
+        ram[idx + 1] = (val >> 8) as u8;
+    }
+
+    // Returns whether a write or erase went through to `ram`.
+    fn write(&mut self, ram: &mut [u8], val: u8) -> bool {
+        let cs: bool = val & EEPROM_CS != 0;
+        let clk: bool = val & EEPROM_CLK != 0;
+        let di: u16 = (val & EEPROM_DI != 0) as u16;
+
+        let mut written: bool = false;
+        if !cs {
+            // Deselecting aborts whatever was in flight
+            self.state = EepromState::Idle;
+        } else if clk && !self.clk {
+            written = self.clock_in(ram, di);
+        }
+        self.cs = cs;
+        self.clk = clk;
+        written
+    }
+
+    fn clock_in(&mut self, ram: &mut [u8], di: u16) -> bool {
+        match &mut self.state {
+            EepromState::Idle => {
+                if di == 1 {
+                    self.state = EepromState::Command;
+                    self.shift = 0;
+                    self.bits = 0;
+                }
+            },
+            EepromState::Command => {
+                self.shift = (self.shift << 1) | di;
+                self.bits += 1;
+                if self.bits == EEPROM_COMMAND_BITS {
+                    return self.execute(ram);
+                }
+            },
+            EepromState::Read { word, bits } => {
+                self.data_out = *word & 0x8000 != 0;
+                *word <<= 1;
+                *bits -= 1;
+                if *bits == 0 {
+                    self.state = EepromState::Idle;
+                }
+            },
+            EepromState::Write { addr, data, bits } => {
+                *data = (*data << 1) | di;
+                *bits -= 1;
+                if *bits == 0 {
+                    let written: bool = self.write_enabled;
+                    if written {
+                        Eeprom::write_word(ram, *addr, *data);
+                    }
+                    self.data_out = true;
+                    self.state = EepromState::Idle;
+                    return written;
+                }
+            },
+            EepromState::WriteAll { data, bits } => {
+                *data = (*data << 1) | di;
+                *bits -= 1;
+                if *bits == 0 {
+                    let written: bool = self.write_enabled;
+                    if written {
+                        for addr in 0..(MBC7_EEPROM_SIZE / 2) as u8 {
+                            Eeprom::write_word(ram, addr, *data);
+                        }
+                    }
+                    self.data_out = true;
+                    self.state = EepromState::Idle;
+                    return written;
+                }
+            },
+        }
+        false
+    }
+
+    fn execute(&mut self, ram: &mut [u8]) -> bool {
+        let opcode: u16 = self.shift >> 8;
+        let addr: u8 = (self.shift & 0x7F) as u8;
+        self.state = EepromState::Idle;
+        match opcode {
+            // READ, a dummy 0 bit goes out first
+            0b10 => {
+                self.data_out = false;
+                self.state = EepromState::Read { word: Eeprom::read_word(ram, addr), bits: 16 };
+            },
+            // WRITE
+            0b01 => self.state = EepromState::Write { addr, data: 0, bits: 16 },
+            // ERASE
+            0b11 => {
+                self.data_out = true;
+                if self.write_enabled {
+                    Eeprom::write_word(ram, addr, 0xFFFF);
+                    return true;
+                }
+            },
+            _ => match (self.shift >> 6) & 0x03 {
+                0b00 => self.write_enabled = false,     // EWDS
+                0b11 => self.write_enabled = true,      // EWEN
+                0b10 => {                               // ERAL
+                    self.data_out = true;
+                    if self.write_enabled {
+                        ram[..MBC7_EEPROM_SIZE].fill(0xFF);
+                        return true;
+                    }
+                },
+                _ => self.state = EepromState::WriteAll { data: 0, bits: 16 },  // WRAL
+            },
+        }
+        false
+    }
+
+    fn read(&self) -> u8 {
+        let mut res: u8 = 0;
+        if self.cs { res |= EEPROM_CS; }
+        if self.clk { res |= EEPROM_CLK; }
+        if self.data_out { res |= EEPROM_DO; }
+        res
+    }
+}
+
+// https://gbdev.io/pandocs/MBC7.html
+pub struct Mbc7 {
+    ram_enabled_1: bool,
+    ram_enabled_2: bool,
+    rom_bank: u8,
+    tilt: (f32, f32),
+    accel_x: u16,
+    accel_y: u16,
+    eeprom: Eeprom,
+}
+
+impl Mbc7 {
+    pub fn new() -> Mbc7 {
+        Mbc7 {
+            ram_enabled_1: false,
+            ram_enabled_2: false,
+            rom_bank: 1,
+            tilt: (0.0, 0.0),
+            accel_x: ACCEL_ERASED,
+            accel_y: ACCEL_ERASED,
+            eeprom: Eeprom::new(),
+        }
+    }
+
+    fn registers_enabled(&self) -> bool {
+        self.ram_enabled_1 && self.ram_enabled_2
+    }
+
+    fn to_accel(g: f32) -> u16 {
+        (ACCEL_CENTER as i32 + (g * ACCEL_ONE_G) as i32) as u16
+    }
+}
+
+impl Mbc for Mbc7 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < ROM1_BASE {
+            rom[rom_offset(rom, 0, addr)]
+        } else {
+            rom[rom_offset(rom, self.rom_bank as usize, addr)]
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x0000..0x2000 => self.ram_enabled_1 = val == 0x0A,
+            0x2000..0x4000 => self.rom_bank = val & 0x7F,
+            0x4000..0x6000 => self.ram_enabled_2 = val == 0x40,
+            _ => (),
+        }
+    }
+
+    // Only 0xA000 - 0xAFFF is decoded, address bits 4 - 7 select the register.
+    fn read_ram(&self, _ram: &[u8], addr: u16) -> u8 {
+        if !self.registers_enabled() || addr >= 0xB000 {
+            return 0xFF;
+        }
+
+        match (addr >> 4) & 0x0F {
+            0x2 => self.accel_x as u8,
+            0x3 => (self.accel_x >> 8) as u8,
+            0x4 => self.accel_y as u8,
+            0x5 => (self.accel_y >> 8) as u8,
+            0x6 => 0x00,
+            0x8 => self.eeprom.read(),
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        if !self.registers_enabled() || addr >= 0xB000 {
+            return false;
+        }
+
+        match (addr >> 4) & 0x0F {
+            0x0 if val == 0x55 => {
+                self.accel_x = ACCEL_ERASED;
+                self.accel_y = ACCEL_ERASED;
+            },
+            // Latching only works after an erase
+            0x1 if val == 0xAA && self.accel_x == ACCEL_ERASED && self.accel_y == ACCEL_ERASED => {
+                self.accel_x = Mbc7::to_accel(self.tilt.0);
+                self.accel_y = Mbc7::to_accel(self.tilt.1);
+            },
+            0x8 => return self.eeprom.write(ram, val),
+            _ => (),
+        }
+        false
+    }
+
+    fn set_accelerometer(&mut self, x: f32, y: f32) {
+        self.tilt = (x, y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ROM_BANK_SIZE;
+
+    const EEPROM: u16 = 0xA080;
+
+    fn enabled() -> Mbc7 {
+        let mut mbc: Mbc7 = Mbc7::new();
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_rom(0x4000, 0x40);
+        mbc
+    }
+
+    // Clocks `count` bits of `val` in, MSB first.
+    fn send(mbc: &mut Mbc7, ram: &mut [u8], val: u16, count: u8) {
+        for i in (0..count).rev() {
+            let di: u8 = if (val >> i) & 1 != 0 { EEPROM_DI } else { 0 };
+            mbc.write_ram(ram, EEPROM, EEPROM_CS | di);
+            mbc.write_ram(ram, EEPROM, EEPROM_CS | EEPROM_CLK | di);
+        }
+    }
+
+    // Start bit, opcode and address.
+    fn command(mbc: &mut Mbc7, ram: &mut [u8], opcode: u16, addr: u8) {
+        mbc.write_ram(ram, EEPROM, 0x00);
+        send(mbc, ram, 0b100 | opcode, 3);
+        send(mbc, ram, addr as u16, 8);
+    }
+
+    fn read_word(mbc: &mut Mbc7, ram: &mut [u8], addr: u8) -> u16 {
+        command(mbc, ram, 0b10, addr);
+        assert_eq!(mbc.read_ram(ram, EEPROM) & EEPROM_DO, 0);
+        let mut word: u16 = 0;
+        for _ in 0..16 {
+            send(mbc, ram, 0, 1);
+            word = (word << 1) | (mbc.read_ram(ram, EEPROM) & EEPROM_DO) as u16;
+        }
+        word
+    }
+
+    #[test]
+    fn rom_banking_and_register_enables() {
+        let rom: Vec<u8> = (0..128 * ROM_BANK_SIZE).map(|i| (i / ROM_BANK_SIZE) as u8).collect();
+        let ram: Vec<u8> = vec![0; MBC7_EEPROM_SIZE];
+        let mut mbc: Mbc7 = Mbc7::new();
+        mbc.write_rom(0x2000, 0x45);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x45);
+        assert_eq!(mbc.read_rom(&rom, 0x0000), 0);
+
+        // Both enables are needed
+        mbc.write_rom(0x0000, 0x0A);
+        assert_eq!(mbc.read_ram(&ram, 0xA020), 0xFF);
+        mbc.write_rom(0x4000, 0x40);
+        assert_eq!(mbc.read_ram(&ram, 0xA060), 0x00);
+        assert_eq!(mbc.read_ram(&ram, 0xB060), 0xFF);
+    }
+
+    #[test]
+    fn accelerometer_latches_once_per_erase() {
+        let mut ram: Vec<u8> = vec![0; MBC7_EEPROM_SIZE];
+        let mut mbc: Mbc7 = enabled();
+        // Powers up erased
+        assert_eq!(mbc.read_ram(&ram, 0xA030), 0x80);
+        assert_eq!(mbc.read_ram(&ram, 0xA020), 0x00);
+        mbc.set_accelerometer(1.0, 0.0);
+        mbc.write_ram(&mut ram, 0xA010, 0xAA);
+        let x: u16 = ACCEL_CENTER + ACCEL_ONE_G as u16;
+        assert_eq!(mbc.read_ram(&ram, 0xA020), x as u8);
+        assert_eq!(mbc.read_ram(&ram, 0xA030), (x >> 8) as u8);
+        assert_eq!(mbc.read_ram(&ram, 0xA040), ACCEL_CENTER as u8);
+        assert_eq!(mbc.read_ram(&ram, 0xA050), (ACCEL_CENTER >> 8) as u8);
+
+        // No new reading without another erase
+        mbc.set_accelerometer(0.0, 0.0);
+        mbc.write_ram(&mut ram, 0xA010, 0xAA);
+        assert_eq!(mbc.read_ram(&ram, 0xA020), x as u8);
+        mbc.write_ram(&mut ram, 0xA000, 0x55);
+        assert_eq!(mbc.read_ram(&ram, 0xA030), 0x80);
+        mbc.write_ram(&mut ram, 0xA010, 0xAA);
+        assert_eq!(mbc.read_ram(&ram, 0xA020), ACCEL_CENTER as u8);
+    }
+
+    #[test]
+    fn eeprom_writes_need_ewen() {
+        let mut ram: Vec<u8> = vec![0; MBC7_EEPROM_SIZE];
+        let mut mbc: Mbc7 = enabled();
+        command(&mut mbc, &mut ram, 0b01, 0x05);
+        send(&mut mbc, &mut ram, 0x1234, 16);
+        assert_eq!(read_word(&mut mbc, &mut ram, 0x05), 0x0000);
+
+        // EWEN
+        command(&mut mbc, &mut ram, 0b00, 0xC0);
+        command(&mut mbc, &mut ram, 0b01, 0x05);
+        send(&mut mbc, &mut ram, 0x1234, 16);
+        assert_eq!(mbc.read_ram(&ram, EEPROM) & EEPROM_DO, EEPROM_DO);
+        assert_eq!(ram[0x0A..0x0C], [0x34, 0x12]);
+        assert_eq!(read_word(&mut mbc, &mut ram, 0x05), 0x1234);
+
+        // ERASE, then EWDS protects the rest
+        command(&mut mbc, &mut ram, 0b11, 0x05);
+        assert_eq!(read_word(&mut mbc, &mut ram, 0x05), 0xFFFF);
+        command(&mut mbc, &mut ram, 0b00, 0x00);
+        command(&mut mbc, &mut ram, 0b01, 0x05);
+        send(&mut mbc, &mut ram, 0x5678, 16);
+        assert_eq!(read_word(&mut mbc, &mut ram, 0x05), 0xFFFF);
+    }
+
+    #[test]
+    fn eeprom_whole_chip_commands() {
+        let mut ram: Vec<u8> = vec![0; MBC7_EEPROM_SIZE];
+        let mut mbc: Mbc7 = enabled();
+        command(&mut mbc, &mut ram, 0b00, 0xC0);
+        // WRAL
+        command(&mut mbc, &mut ram, 0b00, 0x40);
+        send(&mut mbc, &mut ram, 0xBEEF, 16);
+        assert_eq!(read_word(&mut mbc, &mut ram, 0x7F), 0xBEEF);
+        assert!(ram.chunks(2).all(|w| w == [0xEF, 0xBE]));
+        // ERAL
+        command(&mut mbc, &mut ram, 0b00, 0x80);
+        assert!(ram.iter().all(|&b| b == 0xFF));
+    }
+
+    #[test]
+    fn deselecting_aborts_a_command() {
+        let mut ram: Vec<u8> = vec![0; MBC7_EEPROM_SIZE];
+        let mut mbc: Mbc7 = enabled();
+        command(&mut mbc, &mut ram, 0b00, 0xC0);
+        command(&mut mbc, &mut ram, 0b01, 0x05);
+        send(&mut mbc, &mut ram, 0x12, 8);
+        mbc.write_ram(&mut ram, EEPROM, 0x00);
+        send(&mut mbc, &mut ram, 0x34, 8);
+        assert!(ram.iter().all(|&b| b == 0));
+    }
+}
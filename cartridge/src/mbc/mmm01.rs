@@ -0,0 +1,215 @@
+use crate::mbc::{ram_offset, rom_offset, Mbc, ROM1_BASE};
+
+// https://gbdev.io/pandocs/MMM01.html
+// Boots "unmapped" with the last 32 KiB of ROM (the menu) visible. The menu
+// programs the outer bank registers and sets the map bit, after which the
+// chip behaves like an MBC1 restricted to the selected game.
+pub struct Mmm01 {
+    mapped: bool,
+    ram_enabled: bool,
+    rom_low: u8,        // 5 bit, like MBC1 BANK1
+    rom_mid: u8,        // 2 bit
+    rom_high: u8,       // 2 bit
+    rom_mask: u8,       // 4 bit, masks rom_low bits 1 - 4 once mapped
+    ram_low: u8,        // 2 bit
+    ram_high: u8,       // 2 bit
+    ram_mask: u8,       // 2 bit, masks ram_low once mapped
+    mode: u8,
+    mode_locked: bool,
+}
+
+impl Mmm01 {
+    pub fn new() -> Mmm01 {
+        Mmm01 {
+            mapped: false,
+            ram_enabled: false,
+            rom_low: 0,
+            rom_mid: 0,
+            rom_high: 0,
+            rom_mask: 0,
+            ram_low: 0,
+            ram_high: 0,
+            ram_mask: 0,
+            mode: 0,
+            mode_locked: false,
+        }
+    }
+
+    fn outer_rom_bank(&self) -> usize {
+        ((self.rom_high as usize) << 7) | ((self.rom_mid as usize) << 5)
+    }
+
+    fn low_bank(&self) -> usize {
+        if !self.mapped {
+            return 0x1FE;
+        }
+
+        // Only the masked (menu-fixed) bits of the inner bank survive in the low window
+        let fixed: u8 = self.rom_low & (self.rom_mask << 1);
+        self.outer_rom_bank() | fixed as usize
+    }
+
+    fn high_bank(&self) -> usize {
+        if !self.mapped {
+            return 0x1FF;
+        }
+
+        let mut low: u8 = self.rom_low;
+        if low & !(self.rom_mask << 1) & 0x1F == 0 {
+            low |= 0x01;
+        }
+        self.outer_rom_bank() | low as usize
+    }
+
+    fn ram_bank(&self) -> usize {
+        let low: u8 = if self.mode == 1 || !self.mapped { self.ram_low } else { self.ram_low & self.ram_mask };
+        ((self.ram_high as usize) << 2) | low as usize
+    }
+
+    // Bits covered by the mask are frozen after mapping
+    fn masked_write(old: u8, val: u8, mask: u8) -> u8 {
+        (old & mask) | (val & !mask)
+    }
+}
+
+impl Mbc for Mmm01 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < ROM1_BASE {
+            rom[rom_offset(rom, self.low_bank(), addr)]
+        } else {
+            rom[rom_offset(rom, self.high_bank(), addr)]
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x0000..0x2000 => {
+                self.ram_enabled = val & 0x0F == 0x0A;
+                if !self.mapped {
+                    self.ram_mask = (val >> 4) & 0x03;
+                    self.mapped = val & 0x40 != 0;
+                }
+            },
+            0x2000..0x4000 => {
+                if self.mapped {
+                    self.rom_low = Mmm01::masked_write(self.rom_low, val & 0x1F, self.rom_mask << 1);
+                } else {
+                    self.rom_low = val & 0x1F;
+                    self.rom_mid = (val >> 5) & 0x03;
+                }
+            },
+            0x4000..0x6000 => {
+                if self.mapped {
+                    self.ram_low = Mmm01::masked_write(self.ram_low, val & 0x03, self.ram_mask);
+                } else {
+                    self.ram_low = val & 0x03;
+                    self.ram_high = (val >> 2) & 0x03;
+                    self.rom_high = (val >> 4) & 0x03;
+                    self.mode_locked = val & 0x40 != 0;
+                }
+            },
+            _ => {
+                if !self.mode_locked {
+                    self.mode = val & 0x01;
+                }
+                if !self.mapped {
+                    self.rom_mask = (val >> 2) & 0x0F;
+                }
+            },
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled || ram.is_empty() {
+            return 0xFF;
+        }
+        ram[ram_offset(ram, self.ram_bank(), addr)]
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        if !self.ram_enabled || ram.is_empty() {
+            return false;
+        }
+        ram[ram_offset(ram, self.ram_bank(), addr)] = val;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mbc::ERAM_BASE;
+    use crate::{RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+    fn rom(banks: usize) -> Vec<u8> {
+        (0..banks * ROM_BANK_SIZE).map(|i| (i / ROM_BANK_SIZE) as u8).collect()
+    }
+
+    #[test]
+    fn boots_into_the_menu_at_the_end_of_rom() {
+        let rom: Vec<u8> = rom(64);
+        let mbc: Mmm01 = Mmm01::new();
+        assert_eq!(mbc.read_rom(&rom, 0x0000), 62);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 63);
+    }
+
+    #[test]
+    fn mapping_selects_the_game() {
+        let rom: Vec<u8> = rom(64);
+        let mut mbc: Mmm01 = Mmm01::new();
+        // Game at bank 0x20
+        mbc.write_rom(0x2000, 0x20);
+        mbc.write_rom(0x0000, 0x40);
+        assert_eq!(mbc.read_rom(&rom, 0x0000), 0x20);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x21);
+
+        // The game banks within its 32 banks
+        mbc.write_rom(0x2000, 0x03);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x23);
+        mbc.write_rom(0x2000, 0xFF);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x3F);
+
+        // Mapping is for good
+        mbc.write_rom(0x0000, 0x00);
+        mbc.write_rom(0x2000, 0x00);
+        assert_eq!(mbc.read_rom(&rom, 0x0000), 0x20);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x21);
+    }
+
+    #[test]
+    fn rom_mask_freezes_inner_bank_bits() {
+        let rom: Vec<u8> = rom(64);
+        let mut mbc: Mmm01 = Mmm01::new();
+        // 2 bank game at bank 6
+        mbc.write_rom(0x2000, 0x06);
+        mbc.write_rom(0x6000, 0x3C);
+        mbc.write_rom(0x0000, 0x40);
+        assert_eq!(mbc.read_rom(&rom, 0x0000), 0x06);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x07);
+        mbc.write_rom(0x2000, 0x1E);
+        assert_eq!(mbc.read_rom(&rom, 0x4000), 0x07);
+    }
+
+    #[test]
+    fn ram_banks_follow_the_outer_bits() {
+        let rom: Vec<u8> = rom(64);
+        let mut ram: Vec<u8> = vec![0; 16 * RAM_BANK_SIZE];
+        let mut mbc: Mmm01 = Mmm01::new();
+        // RAM banks 4 - 7, all of them left to the game
+        mbc.write_rom(0x4000, 0x04);
+        mbc.write_rom(0x0000, 0x4A);
+        mbc.write_ram(&mut ram, ERAM_BASE, 0x42);
+        assert_eq!(ram[4 * RAM_BANK_SIZE], 0x42);
+        // Mode 0 pins the unmasked bits like MBC1 does
+        mbc.write_rom(0x4000, 0x03);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), 0x42);
+        mbc.write_rom(0x6000, 0x01);
+        mbc.write_ram(&mut ram, ERAM_BASE, 0x43);
+        assert_eq!(ram[7 * RAM_BANK_SIZE], 0x43);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), 0x43);
+        assert_eq!(mbc.read_rom(&rom, 0x0000), 0);
+
+        mbc.write_rom(0x0000, 0x00);
+        assert_eq!(mbc.read_ram(&ram, ERAM_BASE), 0xFF);
+    }
+}
@@ -0,0 +1,123 @@
+mod huc1;
+mod huc3;
+mod mbc1;
+mod mbc2;
+mod mbc3;
+mod mbc5;
+mod mbc6;
+mod mbc7;
+mod mmm01;
+mod rom_only;
+
+use crate::{rtc::{Rtc, TimeSource}, CartridgeError, Header, Mapper, NINTENDO_LOGO, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+pub use huc1::HuC1;
+pub use huc3::HuC3;
+pub use mbc1::Mbc1;
+pub use mbc2::{Mbc2, MBC2_RAM_SIZE};
+pub use mbc3::Mbc3;
+pub use mbc5::Mbc5;
+pub use mbc6::Mbc6;
+pub use mbc7::{Mbc7, MBC7_EEPROM_SIZE};
+pub use mmm01::Mmm01;
+pub use rom_only::RomOnly;
+
+pub const ROM1_BASE: u16 = 0x4000;
+pub const ERAM_BASE: u16 = 0xA000;
+
+// Things happening on the cartridge that the host may want to react to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CartridgeEvent {
+    Rumble(bool),
+    // IR LED switched on/off (HuC1, HuC3)
+    Infrared(bool),
+}
+
+// Memory bank controller. ROM and RAM storage stay in the Cartridge,
+// mappers only translate CPU addresses into offsets and hold their registers.
+pub trait Mbc {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8;
+
+    // Writes to 0x0000 - 0x7FFF never reach ROM, they program the mapper.
+    fn write_rom(&mut self, addr: u16, val: u8);
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8;
+
+    // Returns whether something that goes into the save file, `ram` or the
+    // clock registers, was written, as opposed to mapper registers or
+    // nothing at all.
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool;
+
+    // Called with the T-cycles that passed since the previous call.
+    fn tick(&mut self, _cycles: u32) {}
+
+    fn set_time_source(&mut self, _source: Box<dyn TimeSource>) {}
+
+    fn poll_event(&mut self) -> Option<CartridgeEvent> {
+        None
+    }
+
+    // Host inputs, ignored by mappers without the hardware.
+    // Tilt in g, positive x is right and positive y is down.
+    fn set_accelerometer(&mut self, _x: f32, _y: f32) {}
+
+    fn set_ir_input(&mut self, _light: bool) {}
+
+    fn rtc(&self) -> Option<&Rtc> {
+        None
+    }
+
+    fn rtc_mut(&mut self) -> Option<&mut Rtc> {
+        None
+    }
+}
+
+pub fn create(header: &Header, rom: &[u8]) -> Result<Box<dyn Mbc>, CartridgeError> {
+    match header.cartridge_type.mapper {
+        Mapper::RomOnly => Ok(Box::new(RomOnly)),
+        Mapper::Mbc1 => Ok(Box::new(Mbc1::new(is_mbc1_multicart(rom)))),
+        Mapper::Mbc2 => Ok(Box::new(Mbc2::new())),
+        Mapper::Mmm01 => Ok(Box::new(Mmm01::new())),
+        Mapper::Mbc3 => Ok(Box::new(Mbc3::new(header.cartridge_type.timer))),
+        Mapper::Mbc5 => Ok(Box::new(Mbc5::new(header.cartridge_type.rumble))),
+        Mapper::Mbc6 => Ok(Box::new(Mbc6::new())),
+        Mapper::Mbc7 => Ok(Box::new(Mbc7::new())),
+        Mapper::HuC1 => Ok(Box::new(HuC1::new())),
+        Mapper::HuC3 => Ok(Box::new(HuC3::new())),
+        mapper => Err(CartridgeError::UnsupportedMapper(mapper)),
+    }
+}
+
+// Some mappers carry their own storage instead of what the header declares.
+pub fn ram_size(header: &Header) -> usize {
+    match header.cartridge_type.mapper {
+        Mapper::Mbc2 => MBC2_RAM_SIZE,
+        Mapper::Mbc7 => MBC7_EEPROM_SIZE,
+        _ => header.ram_size(),
+    }
+}
+
+// MBC1M multicarts are 8 Mbit boards that repeat the Nintendo logo at the start
+// of every 16 bank game, there is no header bit for it.
+fn is_mbc1_multicart(rom: &[u8]) -> bool {
+    const MULTICART_SIZE: usize = 64 * ROM_BANK_SIZE;
+    const LOGO_OFFSET: usize = 0x104;
+    if rom.len() != MULTICART_SIZE {
+        return false;
+    }
+
+    (1..4).any(|game| {
+        let base: usize = game * 16 * ROM_BANK_SIZE + LOGO_OFFSET;
+        rom[base..base + NINTENDO_LOGO.len()] == NINTENDO_LOGO
+    })
+}
+
+#[inline(always)]
+pub fn rom_offset(rom: &[u8], bank: usize, addr: u16) -> usize {
+    (bank * ROM_BANK_SIZE + (addr as usize & (ROM_BANK_SIZE - 1))) % rom.len()
+}
+
+#[inline(always)]
+pub fn ram_offset(ram: &[u8], bank: usize, addr: u16) -> usize {
+    (bank * RAM_BANK_SIZE + (addr - ERAM_BASE) as usize) % ram.len()
+}
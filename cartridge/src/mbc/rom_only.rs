@@ -0,0 +1,27 @@
+use crate::mbc::{ram_offset, Mbc};
+
+// 32 KiB cartridges without a mapper, optionally with a single RAM bank.
+pub struct RomOnly;
+
+impl Mbc for RomOnly {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        rom.get(addr as usize).copied().unwrap_or(0xFF)
+    }
+
+    fn write_rom(&mut self, _addr: u16, _val: u8) {}
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if ram.is_empty() {
+            return 0xFF;
+        }
+        ram[ram_offset(ram, 0, addr)]
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, val: u8) -> bool {
+        if ram.is_empty() {
+            return false;
+        }
+        ram[ram_offset(ram, 0, addr)] = val;
+        true
+    }
+}
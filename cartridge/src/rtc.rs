@@ -0,0 +1,339 @@
+use std::time::Instant;
+
+// The RTC crystal runs at 32768 Hz, time sources report elapsed time in its ticks.
+pub const RTC_FREQUENCY: u64 = 32768;
+const CPU_FREQUENCY: u64 = 4_194_304;
+const CYCLES_PER_RTC_TICK: u64 = CPU_FREQUENCY / RTC_FREQUENCY;
+
+// How often (in T-cycles) the wall clock is actually sampled.
+const WALL_POLL_CYCLES: u32 = 0x10000;
+
+pub const DH_DAY_HIGH: u8 = 0x01;
+pub const DH_HALT: u8 = 0x40;
+pub const DH_DAY_CARRY: u8 = 0x80;
+
+// Pluggable source of time for cartridge clocks. It is fed the emulated
+// T-cycles as they pass and returns how many RTC ticks elapsed since the last call.
+pub trait TimeSource {
+    fn advance(&mut self, cycles: u32) -> u64;
+}
+
+// Deterministic time source: RTC time is derived purely from emulated cycles.
+pub struct CycleTimeSource {
+    cycles: u64,
+}
+
+impl CycleTimeSource {
+    pub fn new() -> CycleTimeSource {
+        CycleTimeSource { cycles: 0 }
+    }
+}
+
+impl Default for CycleTimeSource {
+    fn default() -> Self {
+        CycleTimeSource::new()
+    }
+}
+
+impl TimeSource for CycleTimeSource {
+    fn advance(&mut self, cycles: u32) -> u64 {
+        self.cycles += cycles as u64;
+        let ticks: u64 = self.cycles / CYCLES_PER_RTC_TICK;
+        self.cycles %= CYCLES_PER_RTC_TICK;
+        ticks
+    }
+}
+
+// Real time source for frontends: the RTC keeps running at wall clock speed
+// regardless of how fast the emulation itself goes.
+pub struct WallTimeSource {
+    last: Instant,
+    nanos: u128,
+    pending_cycles: u32,
+}
+
+impl WallTimeSource {
+    pub fn new() -> WallTimeSource {
+        WallTimeSource {
+            last: Instant::now(),
+            nanos: 0,
+            pending_cycles: 0,
+        }
+    }
+}
+
+impl Default for WallTimeSource {
+    fn default() -> Self {
+        WallTimeSource::new()
+    }
+}
+
+impl TimeSource for WallTimeSource {
+    fn advance(&mut self, cycles: u32) -> u64 {
+        self.pending_cycles += cycles;
+        if self.pending_cycles < WALL_POLL_CYCLES {
+            return 0;
+        }
+        self.pending_cycles = 0;
+
+        let now: Instant = Instant::now();
+        self.nanos += now.duration_since(self.last).as_nanos();
+        self.last = now;
+
+        let ticks: u128 = self.nanos * RTC_FREQUENCY as u128 / 1_000_000_000;
+        self.nanos -= ticks * 1_000_000_000 / RTC_FREQUENCY as u128;
+        ticks as u64
+    }
+}
+
+// MBC3 real time clock.
+// https://gbdev.io/pandocs/MBC3.html#the-clock-counter-registers
+pub struct Rtc {
+    pub seconds: u8,
+    pub minutes: u8,
+    pub hours: u8,
+    pub days: u16,
+    pub halted: bool,
+    pub day_carry: bool,
+
+    latched: [u8; 5],
+    sub_second: u64,
+    source: Box<dyn TimeSource>,
+}
+
+impl Rtc {
+    pub fn new() -> Rtc {
+        Rtc {
+            seconds: 0,
+            minutes: 0,
+            hours: 0,
+            days: 0,
+            halted: false,
+            day_carry: false,
+            latched: [0; 5],
+            sub_second: 0,
+            source: Box::new(CycleTimeSource::new()),
+        }
+    }
+
+    pub fn set_time_source(&mut self, source: Box<dyn TimeSource>) {
+        self.source = source;
+    }
+
+    pub fn tick(&mut self, cycles: u32) {
+        let ticks: u64 = self.source.advance(cycles);
+        if !self.halted {
+            self.advance_ticks(ticks);
+        }
+    }
+
+    // Also used to catch up with the real time that passed between sessions,
+    // so it must not take time proportional to the seconds elapsed.
+    pub fn advance_ticks(&mut self, ticks: u64) {
+        self.sub_second += ticks;
+        let seconds: u64 = self.sub_second / RTC_FREQUENCY;
+        self.sub_second %= RTC_FREQUENCY;
+        if seconds == 0 {
+            return;
+        }
+
+        let minutes: u64 = Rtc::count(&mut self.seconds, seconds, 60, 0x40);
+        let hours: u64 = Rtc::count(&mut self.minutes, minutes, 60, 0x40);
+        let days: u64 = Rtc::count(&mut self.hours, hours, 24, 0x20) + self.days as u64;
+        if days > 0x1FF {
+            self.day_carry = true;
+        }
+        self.days = (days & 0x1FF) as u16;
+    }
+
+    // Adds `n` to a counter and returns how many times it rolled over.
+    // Counters only roll over when they hit their limit exactly; out of range
+    // values written by the game count up to the register width and wrap to 0
+    // without carrying.
+    fn count(val: &mut u8, n: u64, limit: u8, width: u8) -> u64 {
+        let mut n: u64 = n;
+        if *val >= limit {
+            let to_wrap: u64 = (width - *val) as u64;
+            if n < to_wrap {
+                *val += n as u8;
+                return 0;
+            }
+            n -= to_wrap;
+            *val = 0;
+        }
+        let total: u64 = *val as u64 + n;
+        *val = (total % limit as u64) as u8;
+        total / limit as u64
+    }
+
+    pub fn live_registers(&self) -> [u8; 5] {
+        let mut dh: u8 = (self.days >> 8) as u8 & DH_DAY_HIGH;
+        if self.halted { dh |= DH_HALT; }
+        if self.day_carry { dh |= DH_DAY_CARRY; }
+        [self.seconds, self.minutes, self.hours, self.days as u8, dh]
+    }
+
+    pub fn latched_registers(&self) -> [u8; 5] {
+        self.latched
+    }
+
+    pub fn set_latched_registers(&mut self, regs: [u8; 5]) {
+        self.latched = regs;
+    }
+
+    pub fn latch(&mut self) {
+        self.latched = self.live_registers();
+    }
+
+    // idx: 0 - seconds, 1 - minutes, 2 - hours, 3 - day low, 4 - day high
+    pub fn read(&self, idx: u8) -> u8 {
+        self.latched.get(idx as usize).copied().unwrap_or(0xFF)
+    }
+
+    pub fn write(&mut self, idx: u8, val: u8) {
+        match idx {
+            0 => {
+                self.seconds = val & 0x3F;
+                self.sub_second = 0;
+            },
+            1 => self.minutes = val & 0x3F,
+            2 => self.hours = val & 0x1F,
+            3 => self.days = (self.days & 0x100) | val as u16,
+            4 => {
+                self.days = (self.days & 0xFF) | (((val & DH_DAY_HIGH) as u16) << 8);
+                self.halted = val & DH_HALT != 0;
+                self.day_carry = val & DH_DAY_CARRY != 0;
+            },
+            // Nothing behind the other selections
+            _ => return,
+        }
+        // Writes are visible immediately when reading back
+        self.latched[idx as usize] = self.live_registers()[idx as usize];
+    }
+}
+
+impl Default for Rtc {
+    fn default() -> Self {
+        Rtc::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CYCLES_PER_SECOND: u32 = CPU_FREQUENCY as u32;
+
+    fn rtc_at(days: u16, hours: u8, minutes: u8, seconds: u8) -> Rtc {
+        let mut rtc: Rtc = Rtc::new();
+        rtc.write(0, seconds);
+        rtc.write(1, minutes);
+        rtc.write(2, hours);
+        rtc.write(3, days as u8);
+        rtc.write(4, (days >> 8) as u8);
+        rtc
+    }
+
+    #[test]
+    fn cycle_time_source_counts_crystal_ticks() {
+        let mut source: CycleTimeSource = CycleTimeSource::new();
+        assert_eq!(source.advance(127), 0);
+        assert_eq!(source.advance(1), 1);
+        assert_eq!(source.advance(CYCLES_PER_SECOND), RTC_FREQUENCY);
+
+        let mut rtc: Rtc = Rtc::new();
+        rtc.tick(CYCLES_PER_SECOND - 4);
+        assert_eq!(rtc.seconds, 0);
+        rtc.tick(4);
+        assert_eq!(rtc.seconds, 1);
+    }
+
+    #[test]
+    fn reads_see_the_latched_copy() {
+        let mut rtc: Rtc = rtc_at(0, 1, 2, 3);
+        rtc.latch();
+        rtc.tick(CYCLES_PER_SECOND);
+        assert_eq!(rtc.read(0), 3);
+        assert_eq!(rtc.live_registers()[0], 4);
+        rtc.latch();
+        assert_eq!([rtc.read(0), rtc.read(1), rtc.read(2)], [4, 2, 1]);
+        assert_eq!(rtc.read(5), 0xFF);
+    }
+
+    #[test]
+    fn halt_stops_the_clock() {
+        let mut rtc: Rtc = rtc_at(0, 0, 0, 10);
+        rtc.write(4, DH_HALT);
+        rtc.tick(CYCLES_PER_SECOND);
+        assert_eq!(rtc.seconds, 10);
+        assert_eq!(rtc.read(4), DH_HALT);
+
+        rtc.write(4, 0);
+        rtc.tick(CYCLES_PER_SECOND);
+        assert_eq!(rtc.seconds, 11);
+    }
+
+    #[test]
+    fn seconds_minutes_and_hours_roll_over() {
+        let mut rtc: Rtc = rtc_at(0, 0, 0, 59);
+        rtc.tick(CYCLES_PER_SECOND);
+        assert_eq!((rtc.minutes, rtc.seconds), (1, 0));
+
+        let mut rtc: Rtc = rtc_at(0, 23, 59, 59);
+        rtc.tick(CYCLES_PER_SECOND);
+        assert_eq!((rtc.days, rtc.hours, rtc.minutes, rtc.seconds), (1, 0, 0, 0));
+
+        // Out of range values wrap at the register width without carrying
+        let mut rtc: Rtc = rtc_at(0, 0, 0, 63);
+        rtc.tick(CYCLES_PER_SECOND);
+        assert_eq!((rtc.minutes, rtc.seconds), (0, 0));
+    }
+
+    #[test]
+    fn day_counter_carries_into_bit_8_then_overflows() {
+        let mut rtc: Rtc = rtc_at(255, 23, 59, 59);
+        rtc.tick(CYCLES_PER_SECOND);
+        assert_eq!(rtc.days, 256);
+        assert_eq!(rtc.live_registers()[3..], [0x00, DH_DAY_HIGH]);
+
+        let mut rtc: Rtc = rtc_at(511, 23, 59, 59);
+        rtc.tick(CYCLES_PER_SECOND);
+        assert_eq!(rtc.days, 0);
+        assert!(rtc.day_carry);
+        assert_eq!(rtc.live_registers()[4], DH_DAY_CARRY);
+
+        // The carry stays until cleared by a write
+        rtc.tick(CYCLES_PER_SECOND * 4);
+        assert!(rtc.day_carry);
+        rtc.write(4, 0);
+        assert!(!rtc.day_carry);
+    }
+
+    #[test]
+    fn catching_up_matches_counting_second_by_second() {
+        for (days, hours, minutes, seconds) in [(0, 0, 0, 0), (510, 23, 59, 58), (3, 31, 63, 62), (0, 25, 61, 30)] {
+            let mut stepped: Rtc = rtc_at(days, hours, minutes, seconds);
+            let mut caught_up: Rtc = rtc_at(days, hours, minutes, seconds);
+            for _ in 0..200_000 {
+                stepped.advance_ticks(RTC_FREQUENCY);
+            }
+            caught_up.advance_ticks(200_000 * RTC_FREQUENCY);
+            assert_eq!(stepped.live_registers(), caught_up.live_registers());
+        }
+
+        // A decade away overflows the day counter
+        let mut rtc: Rtc = rtc_at(0, 0, 0, 0);
+        rtc.advance_ticks(3653 * 24 * 3600 * RTC_FREQUENCY + 5);
+        assert_eq!((rtc.days, rtc.hours, rtc.minutes, rtc.seconds), (3653 % 512, 0, 0, 0));
+        assert!(rtc.day_carry);
+        assert_eq!(rtc.sub_second, 5);
+    }
+
+    #[test]
+    fn invalid_registers_are_ignored() {
+        let mut rtc: Rtc = rtc_at(0, 0, 0, 5);
+        rtc.write(5, 0xFF);
+        rtc.write(0xFF, 0xFF);
+        assert_eq!(rtc.live_registers(), [5, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,294 @@
+use std::{fs, io, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+
+use log::{info, warn};
+
+use crate::{rtc::RTC_FREQUENCY, Cartridge};
+
+// VBA/BGB style RTC trailer appended after the RAM dump: live and latched
+// S/M/H/DL/DH as 32 bit little endian words, then a 64 bit UNIX timestamp.
+// Some emulators write a 32 bit timestamp instead (44 bytes), both are accepted.
+const RTC_TRAILER_SIZE: usize = 48;
+const RTC_TRAILER_SIZE_SHORT: usize = 44;
+
+// Flush dirty RAM roughly every emulated second
+pub const FLUSH_PERIOD_CYCLES: u32 = 4_194_304;
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+impl Cartridge {
+    pub fn has_battery(&self) -> bool {
+        self.header.cartridge_type.battery
+    }
+
+    // Path of the save file that sits next to the ROM ("game.gb" -> "game.sav")
+    pub fn save_path_for(rom_path: &Path) -> PathBuf {
+        rom_path.with_extension("sav")
+    }
+
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    pub fn save_data(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = self.ram.clone();
+        if let Some(rtc) = self.rtc() {
+            for reg in rtc.live_registers().iter().chain(rtc.latched_registers().iter()) {
+                data.extend_from_slice(&(*reg as u32).to_le_bytes());
+            }
+            data.extend_from_slice(&unix_now().to_le_bytes());
+        }
+        data
+    }
+
+    // Returns the UNIX timestamp stored in the RTC trailer, if there was one.
+    // Anything but the RAM size, optionally followed by an RTC trailer when the
+    // cartridge has a clock, is rejected and leaves the cartridge untouched.
+    pub fn load_save_data(&mut self, data: &[u8]) -> io::Result<Option<u64>> {
+        let ram_len: usize = self.ram.len();
+        let trailer_len: usize = data.len().wrapping_sub(ram_len);
+        let valid: bool = data.len() == ram_len
+            || (data.len() > ram_len
+                && self.mbc.rtc().is_some()
+                && (trailer_len == RTC_TRAILER_SIZE || trailer_len == RTC_TRAILER_SIZE_SHORT));
+        if !valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("save data is 0x{:X} bytes, expected 0x{:X} of RAM", data.len(), ram_len),
+            ));
+        }
+
+        self.ram.copy_from_slice(&data[..ram_len]);
+        let trailer: &[u8] = &data[ram_len..];
+        if trailer.is_empty() {
+            return Ok(None);
+        }
+        let rtc = self.mbc.rtc_mut().expect("RTC trailer accepted without a clock");
+
+        let word = |i: usize| u32::from_le_bytes(trailer[4 * i..4 * i + 4].try_into().unwrap()) as u8;
+        for i in 0..5 {
+            rtc.write(i as u8, word(i));
+        }
+        rtc.set_latched_registers([word(5), word(6), word(7), word(8), word(9)]);
+
+        let timestamp: u64 = if trailer.len() == RTC_TRAILER_SIZE {
+            u64::from_le_bytes(trailer[40..48].try_into().unwrap())
+        } else {
+            u32::from_le_bytes(trailer[40..44].try_into().unwrap()) as u64
+        };
+        Ok(Some(timestamp))
+    }
+
+    // Loads the save file if it exists and keeps flushing to it from now on.
+    // The RTC is moved forward by the real time that passed since it was written.
+    // On error nothing is attached, so that a bad file is not overwritten.
+    pub fn attach_save_file(&mut self, path: PathBuf) -> io::Result<()> {
+        if !self.needs_save_file() {
+            return Ok(());
+        }
+
+        match fs::read(&path) {
+            Ok(data) => {
+                info!("Loading save file {}", path.display());
+                if let Some(timestamp) = self.load_save_data(&data)?
+                    && let Some(rtc) = self.mbc.rtc_mut()
+                    && !rtc.halted {
+                    rtc.advance_ticks(unix_now().saturating_sub(timestamp) * RTC_FREQUENCY);
+                }
+            },
+            // Write the clock out once even if RAM is left alone, it keeps
+            // running from the timestamp next time
+            Err(err) if err.kind() == io::ErrorKind::NotFound => self.ram_dirty = self.rtc().is_some(),
+            Err(err) => return Err(err),
+        };
+
+        self.save_path = Some(path);
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        let Some(path) = self.save_path.as_ref() else {
+            return Ok(());
+        };
+        fs::write(path, self.save_data())?;
+        self.ram_dirty = false;
+        Ok(())
+    }
+
+    pub(crate) fn periodic_flush(&mut self, cycles: u32) {
+        if self.save_path.is_none() {
+            return;
+        }
+
+        self.flush_cycles += cycles;
+        if self.flush_cycles < FLUSH_PERIOD_CYCLES {
+            return;
+        }
+        self.flush_cycles = 0;
+
+        if self.ram_dirty && let Err(err) = self.flush() {
+            warn!("Failed to write save file: {err}");
+        }
+    }
+
+    // Battery backed RAM or clock that should outlive the session
+    pub fn needs_save_file(&self) -> bool {
+        self.has_battery() && (!self.ram.is_empty() || self.rtc().is_some())
+    }
+}
+
+impl Drop for Cartridge {
+    fn drop(&mut self) {
+        if self.ram_dirty && let Err(err) = self.flush() {
+            warn!("Failed to write save file: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Rtc, NINTENDO_LOGO, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+    // 32 KiB image of the given type with one 8 KiB RAM bank.
+    fn cartridge(cartridge_type: u8) -> Cartridge {
+        let mut rom: Vec<u8> = vec![0; 2 * ROM_BANK_SIZE];
+        rom[0x104..0x134].copy_from_slice(&NINTENDO_LOGO);
+        rom[0x147] = cartridge_type;
+        rom[0x149] = 0x02;
+        Cartridge::from_bytes(rom).unwrap()
+    }
+
+    // MBC3 + TIMER + RAM + BATTERY
+    fn with_clock() -> Cartridge {
+        cartridge(0x10)
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("cartridge-{}-{name}.sav", std::process::id()))
+    }
+
+    // 1d 02:03:04 latched, then 5 more seconds on the live clock
+    fn set_clock(cart: &mut Cartridge) {
+        cart.write(0x0000, 0x0A);
+        for (select, val) in [(0x08, 4), (0x09, 3), (0x0A, 2), (0x0B, 1), (0x0C, 0)] {
+            cart.write(0x4000, select);
+            cart.write(0xA000, val);
+        }
+        cart.write(0x6000, 0x00);
+        cart.write(0x6000, 0x01);
+        cart.mbc.rtc_mut().unwrap().advance_ticks(5 * RTC_FREQUENCY);
+        cart.write(0x4000, 0x00);
+    }
+
+    #[test]
+    fn rtc_trailer_round_trip() {
+        let mut cart: Cartridge = with_clock();
+        set_clock(&mut cart);
+        cart.write(0xA123, 0x42);
+        let before: u64 = unix_now();
+        let data: Vec<u8> = cart.save_data();
+        assert_eq!(data.len(), RAM_BANK_SIZE + RTC_TRAILER_SIZE);
+
+        let mut loaded: Cartridge = with_clock();
+        let timestamp: u64 = loaded.load_save_data(&data).unwrap().unwrap();
+        assert!((before..=unix_now()).contains(&timestamp));
+        assert_eq!(loaded.ram()[0x123], 0x42);
+        let rtc: &Rtc = loaded.rtc().unwrap();
+        assert_eq!(rtc.live_registers(), [9, 3, 2, 1, 0]);
+        assert_eq!(rtc.latched_registers(), [4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn short_rtc_trailer_round_trip() {
+        let mut cart: Cartridge = with_clock();
+        set_clock(&mut cart);
+        let mut data: Vec<u8> = cart.save_data();
+        data.truncate(RAM_BANK_SIZE + 40);
+        data.extend_from_slice(&0x1234_5678u32.to_le_bytes());
+        assert_eq!(data.len(), RAM_BANK_SIZE + RTC_TRAILER_SIZE_SHORT);
+
+        let mut loaded: Cartridge = with_clock();
+        assert_eq!(loaded.load_save_data(&data).unwrap(), Some(0x1234_5678));
+        assert_eq!(loaded.rtc().unwrap().live_registers(), [9, 3, 2, 1, 0]);
+        assert_eq!(loaded.rtc().unwrap().latched_registers(), [4, 3, 2, 1, 0]);
+
+        // A plain RAM dump is fine too
+        let mut loaded: Cartridge = with_clock();
+        assert_eq!(loaded.load_save_data(&data[..RAM_BANK_SIZE]).unwrap(), None);
+    }
+
+    #[test]
+    fn bad_sizes_are_rejected_untouched() {
+        let mut cart: Cartridge = with_clock();
+        for len in [0, RAM_BANK_SIZE - 1, RAM_BANK_SIZE + 1, RAM_BANK_SIZE + 47, RAM_BANK_SIZE + 49] {
+            let err: io::Error = cart.load_save_data(&vec![0xAA; len]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+        assert!(cart.ram().iter().all(|&b| b == 0));
+
+        // MBC5 + RAM + BATTERY, no clock to take a trailer
+        let mut cart: Cartridge = cartridge(0x1B);
+        assert!(cart.load_save_data(&vec![0xAA; RAM_BANK_SIZE + RTC_TRAILER_SIZE]).is_err());
+        assert!(cart.load_save_data(&vec![0xAA; RAM_BANK_SIZE]).is_ok());
+    }
+
+    #[test]
+    fn only_ram_and_clock_writes_dirty_the_save() {
+        let mut cart: Cartridge = with_clock();
+        // RAM disabled, then mapper registers
+        cart.write(0xA000, 0x42);
+        cart.write(0x0000, 0x0A);
+        cart.write(0x4000, 0x0D);
+        cart.write(0x6000, 0x00);
+        cart.write(0x6000, 0x01);
+        cart.write(0xA000, 0x10);
+        assert!(!cart.ram_dirty);
+
+        // Setting the clock, halting it included
+        cart.write(0x4000, 0x0C);
+        cart.write(0xA000, 0x40);
+        assert!(cart.ram_dirty);
+
+        cart.ram_dirty = false;
+        cart.write(0x4000, 0x00);
+        cart.write(0xA000, 0x42);
+        assert!(cart.ram_dirty);
+    }
+
+    #[test]
+    fn clean_cartridges_are_not_written_back() {
+        // MBC5 + RAM + BATTERY
+        let path: PathBuf = temp_path("clean");
+        let mut cart: Cartridge = cartridge(0x1B);
+        cart.attach_save_file(path.clone()).unwrap();
+        cart.read(0xA000);
+        drop(cart);
+        assert!(!path.exists());
+
+        let mut cart: Cartridge = cartridge(0x1B);
+        cart.attach_save_file(path.clone()).unwrap();
+        cart.write(0x0000, 0x0A);
+        cart.write(0xA000, 0x42);
+        drop(cart);
+        let data: Vec<u8> = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(data.len(), RAM_BANK_SIZE);
+        assert_eq!(data[0], 0x42);
+    }
+
+    #[test]
+    fn bad_save_files_are_left_alone() {
+        let path: PathBuf = temp_path("bad");
+        fs::write(&path, [0xAA; 100]).unwrap();
+        let mut cart: Cartridge = cartridge(0x1B);
+        assert!(cart.attach_save_file(path.clone()).is_err());
+        cart.write(0x0000, 0x0A);
+        cart.write(0xA000, 0x42);
+        drop(cart);
+        let data: Vec<u8> = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(data, [0xAA; 100]);
+    }
+}
@@ -1,17 +1,195 @@
-use std::sync::{Mutex, MutexGuard};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
 
-pub struct Clock {
-    counter: u64
+struct Entry<E> {
+    at: u64,
+    // Events due at the same time run in the order they were scheduled
+    seq: u64,
+    event: E,
 }
 
-impl Clock {
-    pub fn new () -> Clock {
-        Clock {
-            counter: 0,
+impl<E> PartialEq for Entry<E> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.at, self.seq) == (other.at, other.seq)
+    }
+}
+
+impl<E> Eq for Entry<E> {}
+
+impl<E> PartialOrd for Entry<E> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<E> Ord for Entry<E> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.at, self.seq).cmp(&(other.at, other.seq))
+    }
+}
+
+// Counts T-cycles and keeps a min-heap of events timestamped in T-cycles.
+// The owner moves time forward and pops the events as they fall due.
+pub struct Scheduler<E> {
+    now: u64,
+    seq: u64,
+    events: BinaryHeap<Reverse<Entry<E>>>,
+}
+
+impl<E: Copy + PartialEq> Scheduler<E> {
+    pub fn new() -> Scheduler<E> {
+        Scheduler {
+            now: 0,
+            seq: 0,
+            events: BinaryHeap::new(),
+        }
+    }
+
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    // Time never goes backwards.
+    pub fn advance_to(&mut self, at: u64) {
+        self.now = self.now.max(at);
+    }
+
+    // Moves time forward with nothing happening: every pending event is
+    // pushed back by the same amount.
+    pub fn postpone(&mut self, cycles: u64) {
+        self.now += cycles;
+        self.events = self.events.drain()
+            .map(|Reverse(entry)| Reverse(Entry { at: entry.at + cycles, ..entry }))
+            .collect();
+    }
+
+    pub fn schedule(&mut self, at: u64, event: E) {
+        self.events.push(Reverse(Entry { at, seq: self.seq, event }));
+        self.seq += 1;
+    }
+
+    pub fn schedule_in(&mut self, cycles: u64, event: E) {
+        self.schedule(self.now + cycles, event);
+    }
+
+    // Drops every pending occurrence of the event.
+    pub fn cancel(&mut self, event: E) {
+        self.events.retain(|Reverse(entry)| entry.event != event);
+    }
+
+    pub fn is_scheduled(&self, event: E) -> bool {
+        self.events.iter().any(|Reverse(entry)| entry.event == event)
+    }
+
+    pub fn next_event_at(&self) -> Option<u64> {
+        self.events.peek().map(|Reverse(entry)| entry.at)
+    }
+
+    // Removes the earliest event due at or before `limit`, along with the
+    // time it was scheduled for.
+    pub fn pop_until(&mut self, limit: u64) -> Option<(u64, E)> {
+        match self.events.peek() {
+            Some(Reverse(entry)) if entry.at <= limit => {
+                let Reverse(entry) = self.events.pop().unwrap();
+                Some((entry.at, entry.event))
+            },
+            _ => None,
         }
     }
+}
+
+impl<E: Copy + PartialEq> Default for Scheduler<E> {
+    fn default() -> Self {
+        Scheduler::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Event {
+        A,
+        B,
+        C,
+    }
 
-    pub fn increment(&mut self) {
-        self.counter += 1;
+    fn drain(scheduler: &mut Scheduler<Event>, limit: u64) -> Vec<(u64, Event)> {
+        std::iter::from_fn(|| scheduler.pop_until(limit)).collect()
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn events_come_out_by_time_then_by_scheduling_order() {
+        let mut scheduler: Scheduler<Event> = Scheduler::new();
+        scheduler.schedule(20, Event::A);
+        scheduler.schedule(10, Event::B);
+        scheduler.schedule(20, Event::C);
+        scheduler.schedule(20, Event::B);
+        scheduler.schedule(5, Event::C);
+        assert_eq!(scheduler.next_event_at(), Some(5));
+        assert_eq!(drain(&mut scheduler, u64::MAX), [
+            (5, Event::C),
+            (10, Event::B),
+            (20, Event::A),
+            (20, Event::C),
+            (20, Event::B),
+        ]);
+        assert_eq!(scheduler.next_event_at(), None);
+    }
+
+    #[test]
+    fn pop_until_stops_at_the_limit() {
+        let mut scheduler: Scheduler<Event> = Scheduler::new();
+        scheduler.schedule(8, Event::A);
+        scheduler.schedule(12, Event::B);
+        scheduler.schedule(13, Event::C);
+        assert_eq!(drain(&mut scheduler, 7), []);
+        assert_eq!(drain(&mut scheduler, 12), [(8, Event::A), (12, Event::B)]);
+        assert_eq!(scheduler.next_event_at(), Some(13));
+        // Popping does not move time
+        assert_eq!(scheduler.now(), 0);
+    }
+
+    #[test]
+    fn cancel_only_drops_the_matching_event() {
+        let mut scheduler: Scheduler<Event> = Scheduler::new();
+        scheduler.schedule(1, Event::A);
+        scheduler.schedule(2, Event::B);
+        scheduler.schedule(3, Event::A);
+        scheduler.schedule(4, Event::C);
+        scheduler.cancel(Event::A);
+        assert!(!scheduler.is_scheduled(Event::A));
+        assert!(scheduler.is_scheduled(Event::B));
+        assert_eq!(drain(&mut scheduler, u64::MAX), [(2, Event::B), (4, Event::C)]);
+    }
+
+    #[test]
+    fn postpone_shifts_time_and_every_event() {
+        let mut scheduler: Scheduler<Event> = Scheduler::new();
+        scheduler.advance_to(10);
+        scheduler.schedule_in(6, Event::A);
+        scheduler.schedule_in(2, Event::B);
+        scheduler.schedule_in(6, Event::C);
+        scheduler.postpone(100);
+        assert_eq!(scheduler.now(), 110);
+        assert_eq!(drain(&mut scheduler, 111), []);
+
+        // Scheduled after the fact, lands in between
+        scheduler.schedule_in(4, Event::A);
+        assert_eq!(drain(&mut scheduler, u64::MAX), [
+            (112, Event::B),
+            (114, Event::A),
+            (116, Event::A),
+            (116, Event::C),
+        ]);
+    }
+
+    #[test]
+    fn time_never_goes_backwards() {
+        let mut scheduler: Scheduler<Event> = Scheduler::new();
+        scheduler.advance_to(50);
+        scheduler.advance_to(20);
+        assert_eq!(scheduler.now(), 50);
+    }
+}
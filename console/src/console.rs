@@ -1,4 +1,11 @@
+mod bus;
+mod dma;
+mod event;
 mod helpers;
+mod joypad;
+mod model;
+mod serial;
+mod timer;
 pub mod types;
 
 mod block_cb;
@@ -6,38 +13,34 @@ mod block_zero;
 mod block_one;
 mod block_two;
 mod block_three;
+mod execute;
 
-use std::io::Write;
-use std::net::TcpStream;
-use std::{marker::PhantomData, net::TcpListener};
-use std::thread;
+use std::marker::PhantomData;
 
 pub use crate::console::helpers::common::debug_addr;
-use crate::console::types::Register;
+use crate::console::bus::{Bus, CGB_BOOT_ROM_SIZE, DMG_BOOT_ROM_SIZE};
+pub use crate::console::joypad::Button;
+pub use crate::console::model::Model;
+use crate::console::types::{Lockup, Register, RegisterSnapshot};
 #[cfg(feature = "debugger")]
 use crate::types::Hookable;
 
-use std::sync::{Arc, Mutex};
+use cartridge::Cartridge;
+use decoder::{Instruction, CB_PREFIX, CB_PREFIXED, UNPREFIXED};
+use log::error;
+use constants::{cond, flag, intr, reg16, reg16mem, reg16stk, reg8, DIV, IE, IF};
+use ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
 
-use clock::Clock;
-use constants::{cond, flag, intr, reg16, reg16mem, reg16stk, reg8, ERAM_BASE, HRAM_BASE, IE, IF, IO_REGS_BASE, LY, OAM_BASE, PALETTES_BASE, PALETTES_END, PROHIBITED_BASE, ROM0_BASE, ROM1_BASE, SB, SC, UNUSED_RAM_BASE, VRAM_BASE, WRAM_BASE};
-use ppu::Ppu;
+// 154 lines of 456 dots
+const CYCLES_PER_FRAME: u64 = 70224;
+// 0x20000 T-cycles
+const SPEED_SWITCH_STALL: u32 = 0x8000;
 
 pub struct Console<'a> {
-    addr_bus: [u8; 0x10000],
-    /*rom_bank_0: [u8; 0x4000],
-    rom_bank_1: [u8; 0x4000],
-    vram: Arc<Mutex<[u8; 0x2000]>>,
-    eram: [u8; 0x2000],
-    wram: [u8; 0x1000],
-    oam: Arc<Mutex<[u8; 0x100]>>,
-    io_regs: Arc<Mutex<[u8; 0x80]>>,
-    hram: [u8; 0x7F],
-    ie: Arc<Mutex<u8>>,*/
+    bus: Bus,
+    model: Model,
     ime: u8,
 
-    clock: Arc<Mutex<Clock>>,
-
     af: Register,
     bc: Register,
     de: Register,
@@ -45,6 +48,12 @@ pub struct Console<'a> {
     sp: Register,
     ip: Register,
     pub pending_ei: bool,
+    halted: bool,
+    halt_bug: bool,
+    stopped: bool,
+    lockup: Option<Lockup>,
+    // M-cycles the CPU sits idle after a speed switch
+    speed_switch_stall: u32,
 
     phantom: PhantomData<&'a u8>,
 
@@ -52,68 +61,71 @@ pub struct Console<'a> {
     hookable: Option<&'a mut dyn Hookable>,
 }
 
-const HEADER_SIZE: usize = 52;
-// For now hardcoded cartridge header
-static HEADER: [u8; HEADER_SIZE] = [
-    0x0,            // NOP
-    0xC3, 0x50, 0x01,    // jp 0x0150
-    // NINTENDO LOGO:
-    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B,
-    0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
-    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E,
-    0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
-    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC,
-    0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
-    //TO BE FILLED
-];
-
 impl<'a> Console<'a> {
-    pub fn init(boot_rom: Vec<u8>) -> Result<Console<'a>, String> {
-        /*let mut tmp_rom_bank_0 = [0; 0x4000];
-        let mut tmp_rom_bank_1 = [0; 0x4000];
-        for i in 0..boot_rom.len() {
-            if i < 0x4000 {
-                tmp_rom_bank_0[i] = boot_rom[i];
-            } else {
-                tmp_rom_bank_1[i - 0x4000] = boot_rom[i];
-            }
-        }*/
-
-        /*for i in 0..HEADER_SIZE {
-            tmp_rom_bank_0[0x100 + i] = HEADER[i];
-        }*/
-
-        let mut tmp_addr_bus = [0; 0x10000];
-        for i in 0..boot_rom.len() {
-            tmp_addr_bus[i] = boot_rom[i];
-        }
-
-        Ok(Console {
-            addr_bus: tmp_addr_bus,
-            /*rom_bank_0: [0; 0x4000],
-            rom_bank_1: [0; 0x4000],
-            vram: Arc::new(Mutex::new([0; 0x2000])),
-            eram: [0; 0x2000],
-            wram: [0; 0x1000],
-            oam: Arc::new(Mutex::new([0; 0x100])),
-            io_regs: Arc::new(Mutex::new([0; 0x80])),
-            hram: [0; 0x7F],
-            ie: Arc::new(Mutex::new(0)),*/
-            ime: 0,
+    // With a boot ROM the console starts from a cold reset at 0x0000 and the
+    // boot ROM hands over to the cartridge by itself. Without one, the CPU and
+    // I/O registers are set to what the boot ROM of the given model leaves
+    // behind and execution starts at the cartridge entry point.
+    pub fn init(cartridge: Cartridge, model: Model, boot_rom: Option<Vec<u8>>) -> Result<Console<'a>, String> {
+        if let Some(rom) = &boot_rom
+            && rom.len() != DMG_BOOT_ROM_SIZE && rom.len() != CGB_BOOT_ROM_SIZE {
+            return Err(format!("Invalid boot ROM size 0x{:X}", rom.len()));
+        }
+        let cold_boot: bool = boot_rom.is_some();
 
-            clock: Arc::new(Mutex::new(Clock::new())),
+        let mut console = Console {
+            bus: Bus::new(cartridge, model, boot_rom),
+            model,
+            ime: 0,
 
-            af: Register { halves: [0xB0, 0x01] },
-            bc: Register { halves: [0x13, 0x00] },
-            de: Register { halves: [0xD8, 0x00] },
-            hl: Register { halves: [0x4D, 0x01] },
-            sp: Register { value: 0xFFFE },
-            ip: Register { value: 0x0100 },
+            af: Register { value: 0 },
+            bc: Register { value: 0 },
+            de: Register { value: 0 },
+            hl: Register { value: 0 },
+            sp: Register { value: 0 },
+            ip: Register { value: 0 },
             pending_ei: false,
+            halted: false,
+            halt_bug: false,
+            stopped: false,
+            lockup: None,
+            speed_switch_stall: 0,
             phantom: PhantomData,
             #[cfg(feature = "debugger")]
             hookable: None,
-        })
+        };
+
+        if !cold_boot {
+            console.skip_boot();
+        }
+        Ok(console)
+    }
+
+    // https://gbdev.io/pandocs/Power_Up_Sequence.html
+    fn skip_boot(&mut self) {
+        let [af, bc, de, hl] = self.model.post_boot_registers(self.bus.cartridge());
+        for (r16stk, val) in [(reg16stk::AF, af), (reg16stk::BC, bc), (reg16stk::DE, de), (reg16stk::HL, hl)] {
+            self.set_r16stk(r16stk, val);
+        }
+        self.set_r16(reg16::SP, 0xFFFE);
+        self.set_ip(0x0100);
+
+        for (addr, val) in self.model.post_boot_io_regs() {
+            self.bus.write_post_boot(addr, val);
+        }
+        self.bus.set_divider(self.model.post_boot_divider());
+    }
+
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    pub fn cartridge(&self) -> &Cartridge {
+        self.bus.cartridge()
+    }
+
+    pub fn cartridge_mut(&mut self) -> &mut Cartridge {
+        self.bus.cartridge_mut()
     }
 
     #[cfg(feature = "debugger")]
@@ -121,15 +133,31 @@ impl<'a> Console<'a> {
         self.hookable = Some(h);
     }
 
+    // Every M-cycle of an instruction goes through here, memory accesses
+    // included, so the rest of the system advances in lockstep with the CPU.
     pub fn mcycle(&mut self) {
-        for _ in 0..4 {
-            self.clock.lock().unwrap().increment();
-        }
+        self.bus.mcycle();
+    }
+
+    // While the CPU idles nothing can change before the next scheduled
+    // event, so time skips straight to the M-cycle it falls in.
+    fn idle(&mut self) {
+        let now: u64 = self.bus.now();
+        let cycles: u64 = match self.bus.next_event_at() {
+            Some(at) if at > now => (at - now).next_multiple_of(4),
+            _ => 4,
+        };
+        self.bus.advance(cycles);
     }
 
     pub fn fetch_byte(&mut self) -> u8 {
         let res: u8 = unsafe { self.get_mem(self.ip.value as usize) };
-        unsafe { self.ip.value += 1 };
+        // The HALT bug makes the CPU read the byte after HALT twice
+        if self.halt_bug {
+            self.halt_bug = false;
+        } else {
+            unsafe { self.ip.value += 1 };
+        }
         res
     }
 
@@ -144,14 +172,19 @@ impl<'a> Console<'a> {
         self.ip.value = val;
     }
 
+    // T-cycles elapsed since power on
+    pub fn cycles(&self) -> u64 {
+        self.bus.now()
+    }
+
     pub fn get_ip(&self) -> u16 {
         unsafe { self.ip.value }
     }
 
     fn stk_push8(&mut self, val: u8) {
-        let sp: u16 = self.get_r16(reg16::SP);
-        self.set_r16(reg16::SP, sp - 1 );
-        self.set_mem(sp as usize - 1, val);
+        let sp: u16 = self.get_r16(reg16::SP).wrapping_sub(1);
+        self.set_r16(reg16::SP, sp);
+        self.set_mem(sp as usize, val);
     }
 
     fn stk_pop8(&mut self) -> u8 {
@@ -172,48 +205,223 @@ impl<'a> Console<'a> {
         low | (high << 8)
     }
 
-    fn step(&mut self) {
-        let curr_ip: u16 = self.get_ip();
-        let bt = self.fetch_byte();
-        if bt == 0xCB {
-            let bt_instr: u8 = self.fetch_byte();
-            block_cb::dispatch(self, bt_instr, curr_ip);
+    // Runs a single instruction, or the interrupt dispatch sequence when an
+    // enabled interrupt is pending.
+    pub fn step(&mut self) {
+        // A locked up CPU never fetches again, but the rest of the system
+        // keeps running.
+        if self.lockup.is_some() {
+            self.idle();
+            return;
         }
-        else {
-            match bt >> 6 {
-                0 => block_zero::dispatch(self, bt, curr_ip),
-                1 => block_one::dispatch(self, bt, curr_ip),
-                2 => block_two::dispatch(self, bt, curr_ip),
-                3 => block_three::dispatch(self, bt, curr_ip),
-                _ => panic!("Invalid instruction"),
-            };
+
+        // Nothing is clocked in STOP mode, only a joypad line going low wakes
+        // the system up. Time still passes so that frames keep their pace.
+        if self.stopped {
+            if !self.bus.joypad().any_pressed() {
+                self.bus.pause(4);
+                return;
+            }
+            self.stopped = false;
+        }
+
+        if self.speed_switch_stall > 0 {
+            self.speed_switch_stall -= 1;
+            if self.pending_interrupts() == 0 {
+                self.mcycle();
+                return;
+            }
+            self.speed_switch_stall = 0;
+        }
+
+        // In halt mode the CPU idles until IE & IF becomes non-zero, whether
+        // or not the interrupt ends up serviced.
+        if self.halted {
+            if self.pending_interrupts() == 0 {
+                self.idle();
+                return;
+            }
+            self.halted = false;
+            if self.ime == 1 {
+                self.mcycle();
+            }
+        }
+
+        if self.ime == 1 && self.pending_interrupts() != 0 {
+            self.handle_interrupt();
+            return;
         }
 
-        // TODO: inspect if this is not too soon
-        if self.pending_ei {
+        // EI takes effect once the instruction that follows it is over, so
+        // the earliest an interrupt can be taken is at the start of the next
+        // step. That instruction still sees it pending, which matters to HALT.
+        let ei_pending: bool = self.pending_ei;
+
+        let curr_ip: u16 = self.get_ip();
+        let instr: Instruction = self.fetch_instruction();
+        // Illegal opcodes hang the CPU before the hook runs so that the
+        // debugger can see it.
+        if let Instruction::Illegal(opcode) = instr {
+            self.lock_up(opcode, curr_ip);
+        }
+        self.call_hook(Some(instr), curr_ip);
+        execute::execute(self, instr);
+
+        // Unless it was a DI
+        if ei_pending && self.pending_ei {
             self.set_ime(1);
             self.pending_ei = false;
         }
     }
 
+    // Fetches the opcode and its immediates, one M-cycle per byte.
+    fn fetch_instruction(&mut self) -> Instruction {
+        let opcode: u8 = self.fetch_byte();
+        let template: Instruction = if opcode == CB_PREFIX {
+            CB_PREFIXED[self.fetch_byte() as usize]
+        } else {
+            UNPREFIXED[opcode as usize]
+        };
+
+        match template.operand_len() {
+            1 => template.with_operand(self.fetch_byte() as u16),
+            2 => template.with_operand(self.fetch_two_bytes()),
+            _ => template,
+        }
+    }
+
     #[cfg(feature = "debugger")]
-    pub fn call_hook(&mut  self, log: String, curr_ip: u16) {
+    pub fn call_hook(&mut  self, instr: Option<Instruction>, curr_ip: u16) {
         if let Some(h) = self.hookable.take() {
-            h.hook(self, log, curr_ip);
+            h.hook(self, instr, curr_ip);
             self.hookable = Some(h);
         }
     }
 
     #[inline(always)]
     #[cfg(not(feature = "debugger"))]
-    pub fn call_hook(&mut  self, _log: String, _curr_ip: u16) {}
+    pub fn call_hook(&mut  self, _instr: Option<Instruction>, _curr_ip: u16) {}
+
+    // https://gbdev.io/pandocs/halt.html
+    pub fn enter_halt(&mut self) {
+        if self.pending_ei && self.pending_interrupts() != 0 {
+            // Right after EI the interrupt is serviced at once, but returns
+            // to the HALT instead of the instruction after it
+            self.set_ip(self.get_ip().wrapping_sub(1));
+        } else if self.ime == 0 && self.pending_interrupts() != 0 {
+            // HALT is skipped and PC fails to increment on the next fetch
+            self.halt_bug = true;
+        } else {
+            self.halted = true;
+        }
+    }
 
-    fn handle_interrupt(&mut self, mask: u8) {
-        self.stk_push16(self.get_ip());
-        self.set_ip(intr::get_jump_vector(mask));
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    // What STOP does depends on the joypad, pending interrupts and KEY1. It
+    // may turn out to be a 1 byte instruction, i.e. the byte after it is run.
+    // https://gbdev.io/pandocs/Reducing_Power_Consumption.html#using-the-stop-instruction
+    pub fn enter_stop(&mut self) {
+        let pending: bool = self.pending_interrupts() != 0;
+
+        // Held buttons prevent STOP, it either does nothing or acts as HALT
+        if self.bus.joypad().any_pressed() {
+            if !pending {
+                self.fetch_byte();
+                self.halted = true;
+            }
+            return;
+        }
+
+        self.bus.write(DIV, 0);
+        if self.model.is_cgb() && self.bus.is_speed_switch_armed() {
+            // With IME set this is supposed to glitch the CPU, which is not
+            // emulated; it is treated like the IME clear case.
+            self.bus.switch_speed();
+            if !pending {
+                self.fetch_byte();
+                self.speed_switch_stall = SPEED_SWITCH_STALL;
+            }
+            return;
+        }
+
+        if !pending {
+            self.fetch_byte();
+        }
+        self.stopped = true;
+        self.bus.ppu_mut().blank();
+    }
+
+    pub fn lock_up(&mut self, opcode: u8, addr: u16) {
+        let lockup: Lockup = Lockup { opcode, addr, regs: self.registers() };
+        error!("{lockup}");
+        self.lockup = Some(lockup);
+    }
+
+    pub fn lockup(&self) -> Option<&Lockup> {
+        self.lockup.as_ref()
+    }
+
+    pub fn registers(&self) -> RegisterSnapshot {
+        unsafe {
+            RegisterSnapshot {
+                af: self.af.value,
+                bc: self.bc.value,
+                de: self.de.value,
+                hl: self.hl.value,
+                sp: self.sp.value,
+                pc: self.ip.value,
+            }
+        }
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    pub fn is_double_speed(&self) -> bool {
+        self.bus.is_double_speed()
+    }
+
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        self.bus.set_button(button, pressed);
+    }
+
+    // Bytes sent over the link port since the last call.
+    pub fn take_serial_output(&mut self) -> Vec<u8> {
+        self.bus.serial_mut().take_output()
+    }
+
+    fn pending_interrupts(&self) -> u8 {
+        self.bus.read(IE) & self.bus.read(IF) & intr::ALL
+    }
+
+    // Two wait M-cycles, PC pushed over two more, and one to jump. The vector
+    // is only picked after the upper byte of PC is pushed, so if that push
+    // overwrites IE the interrupt can get cancelled, in which case the CPU
+    // jumps to 0x0000 instead.
+    // https://gekkio.fi/files/gb-docs/gbctr.pdf; Page 28
+    fn handle_interrupt(&mut self) {
         self.set_ime(0);
-        self.addr_bus[IF] = self.addr_bus[IF] & (!mask);
-        //self.call_hook(intr::intr_to_name(mask), self.get_ip());
+        self.mcycle();
+        self.mcycle();
+
+        let ip: u16 = self.get_ip();
+        self.stk_push8((ip >> 8) as u8);
+        let pending: u8 = self.pending_interrupts();
+        self.stk_push8((ip & 0x00FF) as u8);
+
+        // Lowest bit has the highest priority
+        let mask: u8 = pending & pending.wrapping_neg();
+        if mask != 0 {
+            self.bus.clear_interrupt(mask);
+            self.set_ip(intr::get_jump_vector(mask));
+        } else {
+            self.set_ip(0x0000);
+        }
+        self.mcycle();
     }
 
     pub fn set_ime(&mut self, val: u8) {
@@ -225,35 +433,34 @@ impl<'a> Console<'a> {
 
     // Entry point of the console.
     pub fn execute(&mut self) {
-        /*let vram = self.vram.clone();
-        let oam = self.oam.clone();
-        let io_regs = self.io_regs.clone();
-        let handle = thread::spawn(move || {
-            let mut ppu : Ppu = Ppu::new(vram, oam, io_regs);
-            ppu.execute();
-        });*/
-
-        self.call_hook("".to_owned(), std::u16::MAX);
+        self.call_hook(None, u16::MAX);
         loop {
-            if self.ime == 1 {
-                for i in 0..5 {
-                    let mask: u8 = 1 << i;
-                    if self.addr_bus[IE] & mask == 1 &&
-                        self.addr_bus[IF] & mask == 1 {
-                            self.handle_interrupt(mask);
-                        }
-                    /*if *self.ie.lock().unwrap() & mask == 1 &&
-                        self.io_regs.lock().unwrap()[IF - IO_REGS_BASE] & mask == 1{
-                        self.handle_interrupt(mask);
-                        break;
-                    }*/
-
-                }
-            }
+            self.step();
+        }
+    }
 
+    // Runs until the PPU completes a frame, or for as long as one takes when
+    // the LCD is off or the system is stopped.
+    pub fn run_frame(&mut self) {
+        let frames: u64 = self.bus.ppu().frames();
+        let speed: u64 = if self.is_double_speed() { 2 } else { 1 };
+        let deadline: u64 = self.cycles() + CYCLES_PER_FRAME * speed;
+        while self.bus.ppu().frames() == frames && self.cycles() < deadline {
             self.step();
         }
-        //handle.join().unwrap();
+    }
+
+    // Shade (0 - 3) of every pixel of the last frame.
+    pub fn framebuffer(&self) -> &[u8; SCREEN_WIDTH * SCREEN_HEIGHT] {
+        self.bus.ppu().framebuffer()
+    }
+
+    pub fn colors(&self) -> &[u16; SCREEN_WIDTH * SCREEN_HEIGHT] {
+        self.bus.ppu().colors()
+    }
+
+    pub fn rgba(&self) -> &[u8; SCREEN_WIDTH * SCREEN_HEIGHT * 4] {
+        self.bus.ppu().rgba()
     }
 
     pub fn get_r8(&mut self, idx: u8) -> u8 {
@@ -351,15 +558,15 @@ impl<'a> Console<'a> {
     }
 
     pub fn is_flag_set(&self, flag: u8) -> bool {
-        unsafe { (self.af.halves[0] & (flag as u8))  != 0 }
+        unsafe { (self.af.halves[0] & flag) != 0 }
     }
 
     pub fn set_flag(&mut self, flag: u8) {
-        unsafe { self.af.halves[0] = self.af.halves[0] | (flag as u8); }
+        unsafe { self.af.halves[0] |= flag; }
     }
 
     pub fn clear_flag(&mut self, flag: u8) {
-        unsafe { self.af.halves[0] = self.af.halves[0] & (!(flag as u8)); }
+        unsafe { self.af.halves[0] &= !flag; }
     }
 
     pub fn clear_flags(&mut self, flags: &[u8]) {
@@ -397,97 +604,17 @@ impl<'a> Console<'a> {
     }
 
     pub fn get_mem(&mut self, addr: usize) -> u8 {
-        if addr == LY {
-            0x90 // for gameboy doctor debugging
-        } else {
-            self.mcycle();
-            self.addr_bus[addr]
-        }
+        self.mcycle();
+        self.bus.cpu_read(addr)
     }
 
     pub fn set_mem(&mut self, addr: usize, val: u8) {
         self.mcycle();
-        self.addr_bus[addr] = val;
-    }
-
-    /*pub fn get_mem(&self, addr: usize) -> u8 {
-        match addr {
-            (ROM0_BASE..ROM1_BASE) => self.rom_bank_0[addr],
-            (ROM1_BASE..VRAM_BASE) => self.rom_bank_1[addr - ROM1_BASE],
-            (VRAM_BASE..ERAM_BASE) => {
-                match self.vram.try_lock() {
-                    Ok(guard) => guard[addr - VRAM_BASE],
-                    Err(_) => 0xFF, // garbage value
-                }
-            },
-            (ERAM_BASE..WRAM_BASE) => self.eram[addr - ERAM_BASE],
-            (WRAM_BASE..UNUSED_RAM_BASE) => self.wram[addr - WRAM_BASE], 
-            (OAM_BASE..PROHIBITED_BASE) => {
-                match self.oam.try_lock() {
-                    Ok(guard) => guard[addr - OAM_BASE],
-                    Err(_) => 0xFF // garbage value,
-                }
-            },
-            (IO_REGS_BASE..HRAM_BASE) => {
-                if (PALETTES_BASE..PALETTES_END).contains(&addr) {
-                    match self.palettes_lock.try_lock() {
-                        Ok(_) => self.io_regs.lock().unwrap()[addr - IO_REGS_BASE],
-                        Err(_) => 0xFF, // garbage value
-                    }
-                } else {
-                    self.io_regs.lock().unwrap()[addr - IO_REGS_BASE]
-                }
-            },
-            (HRAM_BASE..IE) => self.hram[addr - HRAM_BASE],
-            IE => *self.ie.lock().unwrap(),
-            _ => panic!("Invalid address")
-        }
+        self.bus.cpu_write(addr, val);
     }
 
-    pub fn set_mem(&mut self, addr: usize, val: u8) {
-        match addr {
-            (ROM0_BASE..ROM1_BASE) => {
-                self.rom_bank_0[addr] = val;
-            }
-            (ROM1_BASE..VRAM_BASE) => {
-                self.rom_bank_1[addr - ROM1_BASE] = val;
-            },
-            (VRAM_BASE..ERAM_BASE) => {
-                match self.vram.try_lock() {
-                    Ok(mut guard) => guard[addr - VRAM_BASE] = val,
-                    Err(_) => (),
-                };
-            },
-            (ERAM_BASE..WRAM_BASE) => {
-                self.eram[addr - ERAM_BASE] = val;
-            },
-            (WRAM_BASE..UNUSED_RAM_BASE) => {
-                self.wram[addr - WRAM_BASE] = val;
-            },
-            (OAM_BASE..PROHIBITED_BASE) => {
-                match self.oam.try_lock() {
-                    Ok(mut guard) => guard[addr - OAM_BASE] = val,
-                    Err(_) => (),
-                }
-            },
-            (IO_REGS_BASE..HRAM_BASE) => {
-                match self.palettes_lock.try_lock() {
-                    Ok(_) => self.io_regs.lock().unwrap()[addr - IO_REGS_BASE] = val,
-                    Err(_) => (),
-                }
-
-                /*if (PALETTES_BASE..PALETTES_END).contains(&addr) {
-                    match self.palettes_lock.try_lock() {
-                        Ok(_) => self.io_regs.lock().unwrap()[addr - IO_REGS_BASE] = val,
-                        Err(_) => (),
-                    }
-                } else {
-                    self.io_regs.lock().unwrap()[addr - IO_REGS_BASE] = val;
-                }*/
-            },
-            (HRAM_BASE..IE) => self.hram[addr - HRAM_BASE] = val,
-            IE => *self.ie.lock().unwrap() = val,
-            _ => panic!("Invalid address")
-        };
-    }*/
+    // Side effect free read for debugging tools, does not advance the clock.
+    pub fn peek_mem(&self, addr: usize) -> u8 {
+        self.bus.read(addr)
+    }
 }
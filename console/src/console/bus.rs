@@ -0,0 +1,403 @@
+use cartridge::Cartridge;
+use clock::Scheduler;
+use ppu::{Mode, Ppu};
+use constants::intr;
+use constants::{BCPS, BGP, BOOT, DIV, DMA, ECHO_RAM_BASE, ERAM_BASE, HRAM_BASE, IE, IF, IO_REGS_BASE, KEY1, LCDC, LYC, OAM_BASE, OCPD, P1, PROHIBITED_BASE, ROM0_BASE, SB, SC, STAT, SVBK, TAC, UNUSED_RAM_BASE, VBK, VRAM_BASE, WRAM_BASE, WX};
+
+use crate::console::dma::Dma;
+use crate::console::event::Event;
+use crate::console::joypad::{Button, Joypad};
+use crate::console::model::Model;
+use crate::console::serial::Serial;
+use crate::console::timer::Timer;
+
+// 8 banks on CGB, 0 is fixed at 0xC000 and SVBK picks the one at 0xD000
+// https://gbdev.io/pandocs/CGB_Registers.html#ff70--svbk-cgb-mode-only-wram-bank
+const WRAM_BANK_SIZE: usize = 0x1000;
+const WRAM_SIZE: usize = WRAM_BANK_SIZE * 8;
+const IO_REGS_SIZE: usize = 0x80;
+const HRAM_SIZE: usize = 0x7F;
+
+// DMG/MGB boot ROMs cover 0x0000 - 0x00FF. CGB ones are 0x900 bytes long and
+// leave 0x0100 - 0x01FF to the cartridge so that the header stays readable.
+const BOOT_ROM_HOLE_BASE: usize = 0x100;
+const BOOT_ROM_HOLE_END: usize = 0x200;
+pub const DMG_BOOT_ROM_SIZE: usize = 0x100;
+pub const CGB_BOOT_ROM_SIZE: usize = 0x900;
+
+// Bits that always read back as 1 for every I/O register (0xFF00 - 0xFF7F).
+// Unmapped registers read as 0xFF.
+// https://gbdev.io/pandocs/Hardware_Reg_List.html
+static IO_READ_MASKS: [u8; IO_REGS_SIZE] = [
+    // P1    SB    SC          DIV   TIMA  TMA   TAC
+    0xC0, 0x00, 0x7E, 0xFF, 0x00, 0x00, 0x00, 0xF8,
+    //                                              IF
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xE0,
+    // NR10  NR11  NR12  NR13  NR14        NR21  NR22
+    0x80, 0x3F, 0x00, 0xFF, 0xBF, 0xFF, 0x3F, 0x00,
+    // NR23  NR24  NR30  NR31  NR32  NR33  NR34
+    0xFF, 0xBF, 0x7F, 0xFF, 0x9F, 0xFF, 0xBF, 0xFF,
+    // NR41  NR42  NR43  NR44  NR50  NR51  NR52
+    0xFF, 0x00, 0x00, 0xBF, 0x00, 0x00, 0x70, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    // Wave RAM
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    // LCDC  STAT  SCY   SCX   LY    LYC   DMA   BGP
+    0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    // OBP0  OBP1  WY    WX
+    0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+];
+
+// Routes CPU accesses to whatever sits behind each address.
+// https://gbdev.io/pandocs/Memory_Map.html
+pub struct Bus {
+    // T-cycles since power on, at the CPU clock
+    scheduler: Scheduler<Event>,
+    cartridge: Cartridge,
+    model: Model,
+    // CGB registers are only there for CGB titles. DMG titles on a CGB are
+    // drawn in the DMG grays, the compatibility palettes the CGB boot ROM
+    // picks from the title are not modelled.
+    cgb_mode: bool,
+    boot_rom: Option<Vec<u8>>,
+    timer: Timer,
+    serial: Serial,
+    ppu: Ppu,
+    dma: Dma,
+    joypad: Joypad,
+    // KEY1
+    double_speed: bool,
+    speed_switch_armed: bool,
+    wram: Box<[u8; WRAM_SIZE]>,
+    svbk: u8,
+    io_regs: [u8; IO_REGS_SIZE],
+    hram: [u8; HRAM_SIZE],
+    ie: u8,
+}
+
+impl Bus {
+    pub fn new(cartridge: Cartridge, model: Model, boot_rom: Option<Vec<u8>>) -> Bus {
+        let cgb_mode: bool = model.cgb_mode(&cartridge);
+        Bus {
+            scheduler: Scheduler::new(),
+            cartridge,
+            model,
+            cgb_mode,
+            boot_rom,
+            timer: Timer::new(),
+            serial: Serial::new(model.is_cgb()),
+            ppu: Ppu::new(cgb_mode),
+            dma: Dma::new(),
+            joypad: Joypad::new(),
+            double_speed: false,
+            speed_switch_armed: false,
+            wram: Box::new([0; WRAM_SIZE]),
+            svbk: 0,
+            io_regs: [0; IO_REGS_SIZE],
+            hram: [0; HRAM_SIZE],
+            ie: 0,
+        }
+    }
+
+    pub fn cartridge(&self) -> &Cartridge {
+        &self.cartridge
+    }
+
+    pub fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cartridge
+    }
+
+    pub fn now(&self) -> u64 {
+        self.scheduler.now()
+    }
+
+    pub fn next_event_at(&self) -> Option<u64> {
+        self.scheduler.next_event_at()
+    }
+
+    pub fn set_divider(&mut self, val: u16) {
+        self.timer.set_divider(val, &mut self.scheduler);
+        self.serial.reschedule(val, &mut self.scheduler);
+    }
+
+    pub fn ppu(&self) -> &Ppu {
+        &self.ppu
+    }
+
+    pub fn ppu_mut(&mut self) -> &mut Ppu {
+        &mut self.ppu
+    }
+
+    pub fn serial_mut(&mut self) -> &mut Serial {
+        &mut self.serial
+    }
+
+    pub fn joypad(&self) -> &Joypad {
+        &self.joypad
+    }
+
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        if self.joypad.set_button(button, pressed) {
+            self.request_interrupt(intr::JOYPAD);
+        }
+    }
+
+    pub fn is_double_speed(&self) -> bool {
+        self.double_speed
+    }
+
+    pub fn is_speed_switch_armed(&self) -> bool {
+        self.speed_switch_armed
+    }
+
+    // Performed by STOP once KEY1 bit 0 was set.
+    // https://gbdev.io/pandocs/CGB_Registers.html#ff4d--key1-cgb-mode-only-prepare-speed-switch
+    pub fn switch_speed(&mut self) {
+        self.double_speed = !self.double_speed;
+        self.speed_switch_armed = false;
+    }
+
+    fn boot_rom_covers(boot_rom: &[u8], addr: usize) -> bool {
+        addr < boot_rom.len() && !(BOOT_ROM_HOLE_BASE..BOOT_ROM_HOLE_END).contains(&addr)
+    }
+
+    // Whether the CPU is locked out of `addr` by DMA or by the PPU mode.
+    fn cpu_blocked(&self, addr: usize) -> bool {
+        self.dma.blocks(addr) || match addr {
+            VRAM_BASE..ERAM_BASE => !self.ppu.vram_accessible(),
+            OAM_BASE..PROHIBITED_BASE => !self.ppu.oam_accessible(),
+            _ => false,
+        }
+    }
+
+    // Accesses made by the CPU, subject to what else is using the buses.
+    // Blocked reads return 0xFF and blocked writes are dropped.
+    pub fn cpu_read(&self, addr: usize) -> u8 {
+        if self.cpu_blocked(addr) {
+            return 0xFF;
+        }
+        self.read(addr)
+    }
+
+    pub fn cpu_write(&mut self, addr: usize, val: u8) {
+        if !self.cpu_blocked(addr) {
+            self.write(addr, val);
+        }
+    }
+
+    // Offset in WRAM of an address in 0xC000 - 0xDFFF, bank 0 selecting 1.
+    fn wram_offset(&self, addr: usize) -> usize {
+        match addr {
+            WRAM_BASE..UNUSED_RAM_BASE => addr - WRAM_BASE,
+            _ => self.svbk.max(1) as usize * WRAM_BANK_SIZE + addr - UNUSED_RAM_BASE,
+        }
+    }
+
+    pub fn read(&self, addr: usize) -> u8 {
+        match addr {
+            ROM0_BASE..VRAM_BASE => match &self.boot_rom {
+                Some(boot_rom) if Bus::boot_rom_covers(boot_rom, addr) => boot_rom[addr],
+                _ => self.cartridge.read(addr as u16),
+            },
+            VRAM_BASE..ERAM_BASE => self.ppu.read_vram(addr),
+            ERAM_BASE..WRAM_BASE => self.cartridge.read(addr as u16),
+            WRAM_BASE..ECHO_RAM_BASE => self.wram[self.wram_offset(addr)],
+            // Echo RAM mirrors 0xC000 - 0xDDFF
+            ECHO_RAM_BASE..OAM_BASE => self.wram[self.wram_offset(addr - (ECHO_RAM_BASE - WRAM_BASE))],
+            OAM_BASE..PROHIBITED_BASE => self.ppu.read_oam(addr),
+            PROHIBITED_BASE..IO_REGS_BASE => 0x00,
+            IO_REGS_BASE..HRAM_BASE => self.read_io(addr),
+            HRAM_BASE..IE => self.hram[addr - HRAM_BASE],
+            IE => self.ie,
+            _ => panic!("Invalid address"),
+        }
+    }
+
+    pub fn write(&mut self, addr: usize, val: u8) {
+        match addr {
+            ROM0_BASE..VRAM_BASE => self.cartridge.write(addr as u16, val),
+            VRAM_BASE..ERAM_BASE => self.ppu.write_vram(addr, val),
+            ERAM_BASE..WRAM_BASE => self.cartridge.write(addr as u16, val),
+            WRAM_BASE..ECHO_RAM_BASE => self.wram[self.wram_offset(addr)] = val,
+            ECHO_RAM_BASE..OAM_BASE => self.wram[self.wram_offset(addr - (ECHO_RAM_BASE - WRAM_BASE))] = val,
+            OAM_BASE..PROHIBITED_BASE => self.ppu.write_oam(addr, val),
+            PROHIBITED_BASE..IO_REGS_BASE => (),
+            IO_REGS_BASE..HRAM_BASE => self.write_io(addr, val),
+            HRAM_BASE..IE => self.hram[addr - HRAM_BASE] = val,
+            IE => self.ie = val,
+            _ => panic!("Invalid address"),
+        }
+    }
+
+    // Puts a register in its state after the boot ROM. The value in DMA is
+    // left over from power on, not from a transfer.
+    pub fn write_post_boot(&mut self, addr: usize, val: u8) {
+        match addr {
+            DMA => self.io_regs[addr - IO_REGS_BASE] = val,
+            _ => self.write(addr, val),
+        }
+    }
+
+    fn read_io(&self, addr: usize) -> u8 {
+        match addr {
+            P1 => self.joypad.read(),
+            SB => self.serial.read(addr),
+            // The CGB clock speed bit is only readable there
+            SC if self.model.is_cgb() => self.serial.read(addr) | 0x7C,
+            SC => self.serial.read(addr) | IO_READ_MASKS[addr - IO_REGS_BASE],
+            DIV..=TAC => self.timer.read(addr, self.scheduler.now()) | IO_READ_MASKS[addr - IO_REGS_BASE],
+            LCDC..=LYC | BGP..=WX => self.ppu.read(addr),
+            VBK | BCPS..=OCPD if self.cgb_mode => self.ppu.read(addr),
+            SVBK if self.cgb_mode => 0xF8 | self.svbk,
+            KEY1 if self.cgb_mode => ((self.double_speed as u8) << 7) | 0x7E | self.speed_switch_armed as u8,
+            _ => self.io_regs[addr - IO_REGS_BASE] | IO_READ_MASKS[addr - IO_REGS_BASE],
+        }
+    }
+
+    fn write_io(&mut self, addr: usize, val: u8) {
+        match addr {
+            P1 => {
+                if self.joypad.write(val) {
+                    self.request_interrupt(intr::JOYPAD);
+                }
+            },
+            SB | SC => {
+                let divider: u16 = self.timer.divider(self.scheduler.now());
+                self.serial.write(addr, val, divider, &mut self.scheduler);
+            },
+            DIV..=TAC => {
+                self.timer.write(addr, val, &mut self.scheduler);
+                // The serial clock comes from the same counter
+                if addr == DIV {
+                    self.serial.reschedule(0, &mut self.scheduler);
+                }
+            },
+            LCDC => {
+                let was_enabled: bool = self.ppu.lcd_enabled();
+                self.ppu.write(addr, val);
+                if was_enabled != self.ppu.lcd_enabled() {
+                    self.scheduler.cancel(Event::PpuMode);
+                    self.scheduler.cancel(Event::PpuDot);
+                    if self.ppu.lcd_enabled() {
+                        self.schedule_ppu(self.scheduler.now(), self.ppu.mode_dots());
+                    }
+                }
+                let interrupts: u8 = self.ppu.take_interrupts();
+                self.request_interrupt(interrupts);
+            },
+            VBK | BCPS..=OCPD if self.cgb_mode => self.ppu.write(addr, val),
+            SVBK if self.cgb_mode => self.svbk = val & 0x07,
+            STAT..=LYC | BGP..=WX => {
+                self.ppu.write(addr, val);
+                // Changing the STAT sources or LYC can raise the STAT line
+                let interrupts: u8 = self.ppu.take_interrupts();
+                self.request_interrupt(interrupts);
+            },
+            DMA => {
+                self.io_regs[addr - IO_REGS_BASE] = val;
+                self.dma.write(val, &mut self.scheduler);
+            },
+            KEY1 if self.cgb_mode => self.speed_switch_armed = val & 1 == 1,
+            // Any non-zero write to BOOT unmaps the boot ROM until the next reset
+            BOOT => {
+                if val != 0 {
+                    self.boot_rom = None;
+                }
+            },
+            _ => self.io_regs[addr - IO_REGS_BASE] = val,
+        }
+    }
+
+    // The PPU does not follow the CPU into double speed mode.
+    fn dots_to_cycles(&self, dots: u64) -> u64 {
+        if self.double_speed { dots * 2 } else { dots }
+    }
+
+    // Mode 3 is drawn an M-cycle at a time so that register writes land in
+    // the middle of the line, the other modes are over in one go.
+    fn schedule_ppu(&mut self, at: u64, dots: u64) {
+        if self.ppu.mode() == Mode::Drawing {
+            self.scheduler.schedule(at + 4, Event::PpuDot);
+        } else {
+            self.scheduler.schedule(at + self.dots_to_cycles(dots), Event::PpuMode);
+        }
+    }
+
+    // Advances everything on the bus by one M-cycle. The CPU calls this
+    // before each of its memory accesses, so the access sees the state the
+    // hardware is in at that point of the instruction.
+    pub fn mcycle(&mut self) {
+        self.advance(4);
+    }
+
+    // Moves time forward by whole M-cycles, running the events that fall due
+    // on the way. Events are only observable at M-cycle boundaries so each
+    // one runs with the clock at the boundary that follows it. In double
+    // speed mode the timer and the serial port follow the CPU clock, the
+    // rest only sees half of the cycles.
+    pub fn advance(&mut self, cycles: u64) {
+        let target: u64 = self.scheduler.now() + cycles;
+        while let Some((at, event)) = self.scheduler.pop_until(target) {
+            self.scheduler.advance_to(at.next_multiple_of(4));
+            match event {
+                Event::TimerIncrement | Event::TimerReload | Event::TimerReloadEnd => {
+                    if self.timer.handle(event, at, &mut self.scheduler) {
+                        self.request_interrupt(intr::TIMER);
+                    }
+                },
+                Event::SerialBit => {
+                    if self.serial.handle(at, &mut self.scheduler) {
+                        self.request_interrupt(intr::SERIAL);
+                    }
+                },
+                Event::PpuMode => {
+                    let dots: u64 = self.ppu.advance_mode();
+                    self.schedule_ppu(at, dots);
+                    let interrupts: u8 = self.ppu.take_interrupts();
+                    self.request_interrupt(interrupts);
+                },
+                Event::PpuDot => {
+                    // The dots of the M-cycle that just went by
+                    match self.ppu.tick(if self.double_speed { 2 } else { 4 }) {
+                        Some(dots) => self.scheduler.schedule(at + self.dots_to_cycles(dots), Event::PpuMode),
+                        None => self.scheduler.schedule(at + 4, Event::PpuDot),
+                    }
+                    let interrupts: u8 = self.ppu.take_interrupts();
+                    self.request_interrupt(interrupts);
+                },
+                Event::DmaStart | Event::DmaByte => {
+                    if let Some((source, oam)) = self.dma.handle(event, at, &mut self.scheduler) {
+                        let val: u8 = self.read(source);
+                        self.ppu.write_oam(oam, val);
+                    }
+                },
+            }
+        }
+        self.scheduler.advance_to(target);
+        self.cartridge.tick(if self.double_speed { cycles / 2 } else { cycles } as u32);
+    }
+
+    // STOP mode: the system clock is halted, so time goes by for the
+    // cartridge and whoever counts cycles but nothing else moves.
+    pub fn pause(&mut self, cycles: u64) {
+        let divider: u16 = self.timer.divider(self.scheduler.now());
+        self.scheduler.postpone(cycles);
+        self.timer.set_divider(divider, &mut self.scheduler);
+        self.cartridge.tick(if self.double_speed { cycles / 2 } else { cycles } as u32);
+    }
+
+    pub fn request_interrupt(&mut self, mask: u8) {
+        self.io_regs[IF - IO_REGS_BASE] |= mask;
+    }
+
+    pub fn clear_interrupt(&mut self, mask: u8) {
+        self.io_regs[IF - IO_REGS_BASE] &= !mask;
+    }
+}
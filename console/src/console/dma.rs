@@ -0,0 +1,67 @@
+use clock::Scheduler;
+use constants::{ECHO_RAM_BASE, IO_REGS_BASE, OAM_BASE, OAM_SIZE};
+
+use crate::console::event::Event;
+
+// Sources above WRAM wrap around to it, like echo RAM does.
+const SOURCE_MIRROR_OFFSET: usize = 0x2000;
+
+// OAM DMA, copying one byte per M-cycle into OAM once the M-cycle after the
+// write to 0xFF46 is over. While it runs the CPU only sees the 0xFF00 page,
+// the rest of the map being on buses the transfer occupies.
+// https://gbdev.io/pandocs/OAM_DMA_Transfer.html
+pub struct Dma {
+    // Source written to 0xFF46, waiting for its start up M-cycle to end
+    pending: Option<usize>,
+    source: usize,
+    // Next byte to copy
+    next: usize,
+    active: bool,
+}
+
+impl Dma {
+    pub fn new() -> Dma {
+        Dma {
+            pending: None,
+            source: 0,
+            next: 0,
+            active: false,
+        }
+    }
+
+    // Whether the CPU is locked out of `addr`.
+    pub fn blocks(&self, addr: usize) -> bool {
+        self.active && addr < IO_REGS_BASE
+    }
+
+    // A transfer already running goes on until the new one takes over.
+    pub fn write(&mut self, val: u8, scheduler: &mut Scheduler<Event>) {
+        let source: usize = (val as usize) << 8;
+        self.pending = Some(if source >= ECHO_RAM_BASE { source - SOURCE_MIRROR_OFFSET } else { source });
+        scheduler.cancel(Event::DmaStart);
+        scheduler.schedule_in(8, Event::DmaStart);
+    }
+
+    // Runs one of the DMA events, `at` being the time it was scheduled for.
+    // Returns the source and OAM addresses of the byte to copy, if any.
+    pub fn handle(&mut self, event: Event, at: u64, scheduler: &mut Scheduler<Event>) -> Option<(usize, usize)> {
+        match event {
+            Event::DmaStart => {
+                self.source = self.pending.take().expect("DMA started without a source");
+                self.next = 0;
+                self.active = true;
+                scheduler.cancel(Event::DmaByte);
+            },
+            Event::DmaByte => (),
+            _ => panic!("Not a DMA event"),
+        }
+        if self.next == OAM_SIZE {
+            self.active = false;
+            return None;
+        }
+        let copy: (usize, usize) = (self.source + self.next, OAM_BASE + self.next);
+        self.next += 1;
+        scheduler.schedule(at + 4, Event::DmaByte);
+        Some(copy)
+    }
+}
@@ -0,0 +1,20 @@
+// What the peripherals schedule on the bus clock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    // Falling edge of the divider bit selected by TAC
+    TimerIncrement,
+    // TMA copied to TIMA one M-cycle after an overflow
+    TimerReload,
+    // Writes to TIMA are ignored until this one
+    TimerReloadEnd,
+    // One bit shifted in and out of SB
+    SerialBit,
+    // The current PPU mode is over
+    PpuMode,
+    // One M-cycle worth of mode 3 drawn
+    PpuDot,
+    // OAM DMA takes over from the previous transfer, if any
+    DmaStart,
+    // One byte copied into OAM
+    DmaByte,
+}
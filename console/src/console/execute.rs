@@ -0,0 +1,102 @@
+use decoder::{AluOp, Instruction, ShiftOp};
+
+use crate::console::{block_cb, block_one, block_three, block_two, block_zero};
+use crate::console::types::{ADD, AND, CARRY, LEFT, NO_CARRY, OR, RIGHT, SUB, XOR};
+use crate::console::Console;
+
+// Immediates are already fetched by the time an instruction gets here.
+pub fn execute(console: &mut Console, instr: Instruction) {
+    match instr {
+        // Block 0
+        Instruction::Nop => (),
+        Instruction::LdR16Imm16(r16, imm16) => block_zero::ld_r16_imm16(r16, imm16, console),
+        Instruction::LdR16MemA(r16) => block_zero::ld_r16mem_a(r16, console),
+        Instruction::LdAR16Mem(r16) => block_zero::ld_a_r16mem(r16, console),
+        Instruction::LdImm16Sp(imm16) => block_zero::ld_imm16_sp(imm16, console),
+        Instruction::IncR16(r16) => block_zero::inc_r16(r16, console),
+        Instruction::DecR16(r16) => block_zero::dec_r16(r16, console),
+        Instruction::AddHlR16(r16) => block_zero::add_hl_r16(r16, console),
+        Instruction::IncR8(r8) => block_zero::inc_r8(r8, console),
+        Instruction::DecR8(r8) => block_zero::dec_r8(r8, console),
+        Instruction::LdR8Imm8(r8, imm8) => block_zero::ld_r8_imm8(r8, imm8, console),
+        Instruction::Rlca => block_zero::rotate_a::<LEFT, CARRY>(console),
+        Instruction::Rrca => block_zero::rotate_a::<RIGHT, CARRY>(console),
+        Instruction::Rla => block_zero::rotate_a::<LEFT, NO_CARRY>(console),
+        Instruction::Rra => block_zero::rotate_a::<RIGHT, NO_CARRY>(console),
+        Instruction::Daa => block_zero::daa(console),
+        Instruction::Cpl => block_zero::cpl(console),
+        Instruction::Scf => block_zero::scf(console),
+        Instruction::Ccf => block_zero::ccf(console),
+        Instruction::JrImm8(imm8) => block_zero::jr_imm8(imm8, console),
+        Instruction::JrCondImm8(cc, imm8) => block_zero::jr_cc_imm8(cc, imm8, console),
+        Instruction::Stop => block_zero::stop(console),
+
+        // Block 1
+        Instruction::LdR8R8(dst, src) => block_one::ld_r8_r8(dst, src, console),
+        Instruction::Halt => block_one::halt(console),
+
+        // Block 2
+        Instruction::AluAR8(op, r8) => match op {
+            AluOp::Add => block_two::arithm_a_r8::<ADD, NO_CARRY>(r8, console),
+            AluOp::Adc => block_two::arithm_a_r8::<ADD, CARRY>(r8, console),
+            AluOp::Sub => block_two::arithm_a_r8::<SUB, NO_CARRY>(r8, console),
+            AluOp::Sbc => block_two::arithm_a_r8::<SUB, CARRY>(r8, console),
+            AluOp::And => block_two::logic_a_r8::<AND>(r8, console),
+            AluOp::Xor => block_two::logic_a_r8::<XOR>(r8, console),
+            AluOp::Or => block_two::logic_a_r8::<OR>(r8, console),
+            AluOp::Cp => block_two::cp_a_r8(r8, console),
+        },
+
+        // Block 3
+        Instruction::AluAImm8(op, imm8) => match op {
+            AluOp::Add => block_three::arithm_a_imm8::<ADD, NO_CARRY>(imm8, console),
+            AluOp::Adc => block_three::arithm_a_imm8::<ADD, CARRY>(imm8, console),
+            AluOp::Sub => block_three::arithm_a_imm8::<SUB, NO_CARRY>(imm8, console),
+            AluOp::Sbc => block_three::arithm_a_imm8::<SUB, CARRY>(imm8, console),
+            AluOp::And => block_three::logic_a_imm8::<AND>(imm8, console),
+            AluOp::Xor => block_three::logic_a_imm8::<XOR>(imm8, console),
+            AluOp::Or => block_three::logic_a_imm8::<OR>(imm8, console),
+            AluOp::Cp => block_three::cp_a_imm8(imm8, console),
+        },
+        Instruction::RetCond(cc) => block_three::ret_cond(cc, console),
+        Instruction::Ret => block_three::ret(console),
+        Instruction::Reti => block_three::reti(console),
+        Instruction::JpCondImm16(cc, imm16) => block_three::jp_cc_imm16(cc, imm16, console),
+        Instruction::JpImm16(imm16) => block_three::jp_imm16(imm16, console),
+        Instruction::JpHl => block_three::jp_hl(console),
+        Instruction::CallCondImm16(cc, imm16) => block_three::call_cc_imm16(cc, imm16, console),
+        Instruction::CallImm16(imm16) => block_three::call_imm16(imm16, console),
+        Instruction::Rst(tgt3) => block_three::rst_tgt3(tgt3, console),
+        Instruction::Pop(r16stk) => block_three::pop_r16stk(r16stk, console),
+        Instruction::Push(r16stk) => block_three::push_r16stk(r16stk, console),
+        Instruction::LdhCA => block_three::ldh_c_a(console),
+        Instruction::LdhImm8A(imm8) => block_three::ldh_imm8_a(imm8, console),
+        Instruction::LdImm16A(imm16) => block_three::ld_imm16_a(imm16, console),
+        Instruction::LdhAC => block_three::ldh_a_c(console),
+        Instruction::LdhAImm8(imm8) => block_three::ldh_a_imm8(imm8, console),
+        Instruction::LdAImm16(imm16) => block_three::ld_a_imm16(imm16, console),
+        Instruction::AddSpImm8(imm8) => block_three::add_sp_imm8(imm8, console),
+        Instruction::LdHlSpImm8(imm8) => block_three::ld_hl_sp_imm8(imm8, console),
+        Instruction::LdSpHl => block_three::ld_sp_hl(console),
+        Instruction::Di => block_three::di(console),
+        Instruction::Ei => block_three::ei(console),
+        // The lock up happens before the hook is called, see Console::step
+        Instruction::Illegal(_) => (),
+        Instruction::Prefix => panic!("CB prefix is never executed on its own"),
+
+        // CB prefixed block
+        Instruction::Shift(op, r8) => match op {
+            ShiftOp::Rlc => block_cb::rotate::<LEFT, CARRY>(r8, console),
+            ShiftOp::Rrc => block_cb::rotate::<RIGHT, CARRY>(r8, console),
+            ShiftOp::Rl => block_cb::rotate::<LEFT, NO_CARRY>(r8, console),
+            ShiftOp::Rr => block_cb::rotate::<RIGHT, NO_CARRY>(r8, console),
+            ShiftOp::Sla => block_cb::shift::<LEFT>(r8, console),
+            ShiftOp::Sra => block_cb::shift::<RIGHT>(r8, console),
+            ShiftOp::Swap => block_cb::swap_r8(r8, console),
+            ShiftOp::Srl => block_cb::srl_r8(r8, console),
+        },
+        Instruction::Bit(b3, r8) => block_cb::bit_b3_r8(b3, r8, console),
+        Instruction::Res(b3, r8) => block_cb::res_b3_r8(b3, r8, console),
+        Instruction::Set(b3, r8) => block_cb::set_b3_r8(b3, r8, console),
+    }
+}
@@ -1,40 +1,15 @@
 use constants::{flag, reg8};
+use decoder::Instruction;
 use log::debug;
 
 use crate::console::{helpers::{bit_ops::{carry, half_carry}}, types::{BitFlag, ADD_VAL, AND_VAL, CARRY_VAL, LEFT_VAL, NO_CARRY_VAL, OR_VAL, RIGHT_VAL, SUB_VAL, XOR_VAL}, Console};
 
-fn log_arithm_a<OP: BitFlag, C: BitFlag>(console: &mut Console, operand: u8, arg_type: usize, curr_ip: u16) {
-    let arg: String = match arg_type {
-        reg8::MAX_REG8 => format!("0x{:04X}", operand),
-        _ => reg8::reg_to_name(arg_type as u8),
-    };
-
-    match OP::VALUE {
-        ADD_VAL => {
-            match C::VALUE {
-                CARRY_VAL => console.call_hook(format!("ADC A, {arg}"), curr_ip),
-                NO_CARRY_VAL => console.call_hook(format!("ADD A, {arg}"), curr_ip),
-                _ => panic!("Flag value out of range (possible values are: CARRY_VAL, NO_CARRY_VAL)"),
-            }
-        },
-        SUB_VAL => {
-            match C::VALUE {
-                CARRY_VAL => console.call_hook(format!("SBC A, {arg}"), curr_ip),
-                NO_CARRY_VAL => console.call_hook(format!("SUB A, {arg}"), curr_ip),
-                _ => panic!("Flag value out of range (possible values are: CARRY_VAL, NO_CARRY_VAL)"),
-            }
-        },
-        _ => panic!("Flag value out of range (possible values are: ADD_VAL, SUB_VAL)"),
-    };
-}
-
 #[inline(always)]
-pub fn debug_addr(addr: u16, expr: String) {
-    debug!("0x{:04X}: {expr}", addr);
+pub fn debug_addr(addr: u16, instr: &Instruction) {
+    debug!("0x{:04X}: {instr}", addr);
 }
 
-pub fn arithm_a_operand<OP: BitFlag, C: BitFlag>(operand: u8, console: &mut Console, arg_type: u8, curr_ip: u16) {
-    log_arithm_a::<OP, C>(console, operand, arg_type as usize, curr_ip);
+pub fn arithm_a_operand<OP: BitFlag, C: BitFlag>(operand: u8, console: &mut Console) {
     let mut carry: u8 = 0;
     if C::VALUE == CARRY_VAL && console.is_flag_set(flag::C) {
         carry = 1;
@@ -88,7 +63,7 @@ pub fn cp_a_operand(operand: u8, console: &mut Console) {
     console.clear_or_set_flag(carry::sub_8(a_val, operand, 0), flag::C);
 }
 
-pub fn rotate_operand<DIR: BitFlag, C: BitFlag>(r8: u8, console: &mut Console, curr_ip: u16) {
+pub fn rotate_operand<DIR: BitFlag, C: BitFlag>(r8: u8, console: &mut Console) {
     let curr_c: u8 = console.is_flag_set(flag::C) as u8;
 
     let mut reg: u8;
@@ -101,17 +76,10 @@ pub fn rotate_operand<DIR: BitFlag, C: BitFlag>(r8: u8, console: &mut Console, c
             c = reg >> 7;
             match C::VALUE {
                 CARRY_VAL => {
-                    if r8 == reg8::EA { console.call_hook(format!("RLCA"), curr_ip); }
-                    else { console.call_hook(format!("RLC {}", reg8::reg_to_name(r8)), curr_ip); }
-
                     reg = reg << 1 | c;
                 },
                 NO_CARRY_VAL => {
-                    if r8 == reg8::EA { console.call_hook(format!("RLA"), curr_ip); }
-                    else { console.call_hook(format!("RL {}", reg8::reg_to_name(r8)), curr_ip); }
-
                     reg = reg << 1 | curr_c;
-
                 },
                 _ => panic!("Invalid carry"),
             }
@@ -120,15 +88,9 @@ pub fn rotate_operand<DIR: BitFlag, C: BitFlag>(r8: u8, console: &mut Console, c
             c = reg & 0x1;
             match C::VALUE {
                 CARRY_VAL => {
-                    if r8 == reg8::EA { console.call_hook(format!("RRCA"), curr_ip); }
-                    else { console.call_hook(format!("RRC {}", reg8::reg_to_name(r8)), curr_ip); }
-
                     reg = reg >> 1 | c << 7;
                 },
                 NO_CARRY_VAL => {
-                    if r8 == reg8::EA { console.call_hook(format!("RRA"), curr_ip); }
-                    else { console.call_hook(format!("RR {}", reg8::reg_to_name(r8)), curr_ip); }
-
                     reg = reg >> 1 | curr_c << 7;
                 },
                 _ => panic!("Invalid carry"),
@@ -0,0 +1,81 @@
+const SELECT_DPAD: u8 = 0b01_0000;
+const SELECT_BUTTONS: u8 = 0b10_0000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Button {
+    Right,
+    Left,
+    Up,
+    Down,
+    A,
+    B,
+    Select,
+    Start,
+}
+
+// P1, the button matrix. Both the select bits and the input lines are active
+// low; a line going low while selected requests the joypad interrupt.
+// https://gbdev.io/pandocs/Joypad_Input.html
+pub struct Joypad {
+    select: u8,
+    dpad: u8,
+    buttons: u8,
+}
+
+impl Joypad {
+    pub fn new() -> Joypad {
+        Joypad {
+            select: SELECT_DPAD | SELECT_BUTTONS,
+            dpad: 0,
+            buttons: 0,
+        }
+    }
+
+    fn lines(&self) -> u8 {
+        let mut pressed: u8 = 0;
+        if self.select & SELECT_DPAD == 0 {
+            pressed |= self.dpad;
+        }
+        if self.select & SELECT_BUTTONS == 0 {
+            pressed |= self.buttons;
+        }
+        !pressed & 0x0F
+    }
+
+    // True if any of the currently selected lines is held low.
+    pub fn any_pressed(&self) -> bool {
+        self.lines() != 0x0F
+    }
+
+    pub fn read(&self) -> u8 {
+        0xC0 | self.select | self.lines()
+    }
+
+    // Returns true when the joypad interrupt should be requested.
+    pub fn write(&mut self, val: u8) -> bool {
+        let old: u8 = self.lines();
+        self.select = val & (SELECT_DPAD | SELECT_BUTTONS);
+        old & !self.lines() != 0
+    }
+
+    // Returns true when the joypad interrupt should be requested.
+    pub fn set_button(&mut self, button: Button, pressed: bool) -> bool {
+        let old: u8 = self.lines();
+        let (group, bit) = match button {
+            Button::Right => (&mut self.dpad, 0),
+            Button::Left => (&mut self.dpad, 1),
+            Button::Up => (&mut self.dpad, 2),
+            Button::Down => (&mut self.dpad, 3),
+            Button::A => (&mut self.buttons, 0),
+            Button::B => (&mut self.buttons, 1),
+            Button::Select => (&mut self.buttons, 2),
+            Button::Start => (&mut self.buttons, 3),
+        };
+        if pressed {
+            *group |= 1 << bit;
+        } else {
+            *group &= !(1 << bit);
+        }
+        old & !self.lines() != 0
+    }
+}
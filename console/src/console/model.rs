@@ -0,0 +1,112 @@
+use cartridge::{Cartridge, CgbSupport};
+use constants::{BGP, DMA, IF, LCDC, NR52, OBP0, OBP1, P1, SC, STAT, TAC};
+
+// Hardware revisions that differ in the state the boot ROM leaves behind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Model {
+    Dmg0,
+    Dmg,
+    Mgb,
+    Sgb,
+    Sgb2,
+    Cgb,
+    Agb,
+}
+
+// Audio registers are the same on every model once the boot ROM is done
+// (NR10 - NR51, 0xFF10 - 0xFF25).
+static POST_BOOT_AUDIO: [u8; 0x16] = [
+    0x80, 0xBF, 0xF3, 0xFF, 0xBF, 0xFF, 0x3F, 0x00,
+    0xFF, 0xBF, 0x7F, 0xFF, 0x9F, 0xFF, 0xBF, 0xFF,
+    0xFF, 0x00, 0x00, 0xBF, 0x77, 0xF3,
+];
+const AUDIO_BASE: usize = 0xFF10;
+
+impl Model {
+    pub fn from_name(name: &str) -> Option<Model> {
+        match name.to_ascii_lowercase().as_str() {
+            "dmg0" => Some(Model::Dmg0),
+            "dmg" => Some(Model::Dmg),
+            "mgb" => Some(Model::Mgb),
+            "sgb" => Some(Model::Sgb),
+            "sgb2" => Some(Model::Sgb2),
+            "cgb" => Some(Model::Cgb),
+            "agb" => Some(Model::Agb),
+            _ => None,
+        }
+    }
+
+    pub fn is_cgb(&self) -> bool {
+        matches!(self, Model::Cgb | Model::Agb)
+    }
+
+    // The default model for a cartridge, CGB for the ones that know about it.
+    pub fn for_cartridge(cartridge: &Cartridge) -> Model {
+        if cartridge.header().cgb == CgbSupport::None { Model::Dmg } else { Model::Cgb }
+    }
+
+    // CGB features are only unlocked for cartridges that declare support in
+    // their header, the others run in DMG compatibility mode.
+    // https://gbdev.io/pandocs/The_Cartridge_Header.html#0143--cgb-flag
+    pub fn cgb_mode(&self, cartridge: &Cartridge) -> bool {
+        self.is_cgb() && cartridge.header().cgb != CgbSupport::None
+    }
+
+    pub fn is_sgb(&self) -> bool {
+        matches!(self, Model::Sgb | Model::Sgb2)
+    }
+
+    // AF, BC, DE, HL at 0x0100
+    // https://gbdev.io/pandocs/Power_Up_Sequence.html#cpu-registers
+    pub fn post_boot_registers(&self, cartridge: &Cartridge) -> [u16; 4] {
+        // DMG boot ROMs leave H and C set unless the header checksum is 0
+        let dmg_flags: u16 = if cartridge.header().header_checksum == 0 { 0x80 } else { 0xB0 };
+        let cgb_mode: bool = self.cgb_mode(cartridge);
+
+        match self {
+            Model::Dmg0 => [0x0100, 0xFF13, 0x00C1, 0x8403],
+            Model::Dmg => [0x0100 | dmg_flags, 0x0013, 0x00D8, 0x014D],
+            Model::Mgb => [0xFF00 | dmg_flags, 0x0013, 0x00D8, 0x014D],
+            Model::Sgb => [0x0100, 0x0014, 0x0000, 0xC060],
+            Model::Sgb2 => [0xFF00, 0x0014, 0x0000, 0xC060],
+            Model::Cgb if cgb_mode => [0x1180, 0x0000, 0xFF56, 0x000D],
+            Model::Cgb => [0x1180, 0x0000, 0x0008, 0x007C],
+            Model::Agb if cgb_mode => [0x1100, 0x0100, 0xFF56, 0x000D],
+            Model::Agb => [0x1100, 0x0100, 0x0008, 0x007C],
+        }
+    }
+
+    // Internal 16 bit divider at 0x0100, the upper byte is what DIV reads.
+    // Not part of post_boot_io_regs as writing DIV resets the counter.
+    // Only the DMG family values are well documented, the rest are the
+    // figures other emulators settled on.
+    pub fn post_boot_divider(&self) -> u16 {
+        match self {
+            Model::Dmg0 => 0x1830,
+            Model::Dmg | Model::Mgb => 0xABCC,
+            Model::Sgb | Model::Sgb2 => 0xD85C,
+            Model::Cgb | Model::Agb => 0x267C,
+        }
+    }
+
+    // https://gbdev.io/pandocs/Power_Up_Sequence.html#hardware-registers
+    pub fn post_boot_io_regs(&self) -> Vec<(usize, u8)> {
+        let mut regs: Vec<(usize, u8)> = vec![
+            (P1, if self.is_sgb() { 0xFF } else { 0xCF }),
+            (SC, if self.is_cgb() { 0x7F } else { 0x7E }),
+            (TAC, 0xF8),
+            (IF, 0xE1),
+            (NR52, if self.is_sgb() { 0xF0 } else { 0xF1 }),
+            (LCDC, 0x91),
+            (STAT, if *self == Model::Dmg0 { 0x81 } else { 0x85 }),
+            (DMA, if self.is_cgb() { 0x00 } else { 0xFF }),
+            (BGP, 0xFC),
+            (OBP0, 0xFF),
+            (OBP1, 0xFF),
+        ];
+        for (i, val) in POST_BOOT_AUDIO.iter().enumerate() {
+            regs.push((AUDIO_BASE + i, *val));
+        }
+        regs
+    }
+}
@@ -0,0 +1,94 @@
+use clock::Scheduler;
+use constants::{SB, SC};
+
+use crate::console::event::Event;
+
+const SC_TRANSFER: u8 = 0x80;
+const SC_FAST_CLOCK: u8 = 0x02;
+const SC_INTERNAL_CLOCK: u8 = 0x01;
+
+// Divider bit whose falling edge shifts one bit out, 8192 Hz and 262144 Hz.
+const NORMAL_CLOCK_BIT: u8 = 8;
+const FAST_CLOCK_BIT: u8 = 3;
+
+// SB/SC with nothing plugged into the link port: every bit shifted in is 1.
+// Bytes sent with the internal clock are kept around since test ROMs report
+// their results through the serial port, until the frontend takes them.
+// https://gbdev.io/pandocs/Serial_Data_Transfer_(Link_Cable).html
+pub struct Serial {
+    sb: u8,
+    sc: u8,
+    // Only the CGB has the fast clock
+    cgb: bool,
+    bits_left: u8,
+    sending: u8,
+    output: Vec<u8>,
+}
+
+impl Serial {
+    pub fn new(cgb: bool) -> Serial {
+        Serial {
+            sb: 0,
+            sc: 0,
+            cgb,
+            bits_left: 0,
+            sending: 0,
+            output: Vec::new(),
+        }
+    }
+
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    fn clock_bit(&self) -> u8 {
+        if self.cgb && self.sc & SC_FAST_CLOCK != 0 { FAST_CLOCK_BIT } else { NORMAL_CLOCK_BIT }
+    }
+
+    // The serial clock is derived from the same system counter as DIV and a
+    // bit is shifted on each falling edge of the selected counter bit.
+    pub fn reschedule(&mut self, divider: u16, scheduler: &mut Scheduler<Event>) {
+        scheduler.cancel(Event::SerialBit);
+        if self.bits_left == 0 {
+            return;
+        }
+        let period: u64 = 2 << self.clock_bit();
+        let phase: u64 = divider as u64 % period;
+        scheduler.schedule_in(period - phase, Event::SerialBit);
+    }
+
+    // Returns true when the serial interrupt should be requested.
+    pub fn handle(&mut self, at: u64, scheduler: &mut Scheduler<Event>) -> bool {
+        self.sb = (self.sb << 1) | 1;
+        self.bits_left -= 1;
+        if self.bits_left > 0 {
+            scheduler.schedule(at + (2 << self.clock_bit()), Event::SerialBit);
+            return false;
+        }
+        self.sc &= !SC_TRANSFER;
+        self.output.push(self.sending);
+        true
+    }
+
+    pub fn read(&self, addr: usize) -> u8 {
+        match addr {
+            SB => self.sb,
+            SC => self.sc,
+            _ => panic!("Invalid serial register"),
+        }
+    }
+
+    pub fn write(&mut self, addr: usize, val: u8, divider: u16, scheduler: &mut Scheduler<Event>) {
+        match addr {
+            SB => self.sb = val,
+            SC => {
+                self.sc = if self.cgb { val & 0x83 } else { val & 0x81 };
+                // Without a partner an external clock never ticks
+                self.bits_left = if self.sc & SC_TRANSFER != 0 && self.sc & SC_INTERNAL_CLOCK != 0 { 8 } else { 0 };
+                self.sending = self.sb;
+                self.reschedule(divider, scheduler);
+            },
+            _ => panic!("Invalid serial register"),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use clock::Scheduler;
+use constants::{DIV, TAC, TIMA, TMA};
+
+use crate::console::event::Event;
+
+const TAC_ENABLE: u8 = 0b100;
+const TAC_CLOCK_SELECT: u8 = 0b011;
+
+// Divider bit whose falling edge increments TIMA, indexed by TAC clock select.
+static TAC_DIVIDER_BITS: [u8; 4] = [9, 3, 5, 7];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Reload {
+    Idle,
+    // TIMA overflowed during the last M-cycle and reads 0x00.
+    Pending,
+    // TMA was copied to TIMA during the last M-cycle.
+    Reloaded,
+}
+
+// DIV/TIMA/TMA/TAC, driven by the falling edge of one bit of the 16 bit
+// system counter. DIV is the upper byte of that counter.
+// The counter is not stepped, it is derived from the time elapsed since it
+// was last set, and every falling edge that matters is an event.
+// https://gbdev.io/pandocs/Timer_Obscure_Behaviour.html
+pub struct Timer {
+    // Counter value at `epoch`
+    divider_base: u16,
+    epoch: u64,
+    tima: u8,
+    tma: u8,
+    tac: u8,
+    reload: Reload,
+}
+
+impl Timer {
+    pub fn new() -> Timer {
+        Timer {
+            divider_base: 0,
+            epoch: 0,
+            tima: 0,
+            tma: 0,
+            tac: 0,
+            reload: Reload::Idle,
+        }
+    }
+
+    pub fn divider(&self, now: u64) -> u16 {
+        self.divider_base.wrapping_add((now - self.epoch) as u16)
+    }
+
+    pub fn set_divider(&mut self, val: u16, scheduler: &mut Scheduler<Event>) {
+        self.divider_base = val;
+        self.epoch = scheduler.now();
+        self.reschedule(scheduler);
+    }
+
+    fn divider_bit(&self) -> u8 {
+        TAC_DIVIDER_BITS[(self.tac & TAC_CLOCK_SELECT) as usize]
+    }
+
+    // AND of the enable bit and the selected divider bit.
+    fn signal(&self, now: u64) -> bool {
+        self.tac & TAC_ENABLE != 0 && (self.divider(now) >> self.divider_bit()) & 1 == 1
+    }
+
+    // The selected bit falls each time the counter reaches a multiple of
+    // twice its weight.
+    fn reschedule(&mut self, scheduler: &mut Scheduler<Event>) {
+        scheduler.cancel(Event::TimerIncrement);
+        if self.tac & TAC_ENABLE == 0 {
+            return;
+        }
+        let period: u64 = 2 << self.divider_bit();
+        let phase: u64 = self.divider(scheduler.now()) as u64 % period;
+        scheduler.schedule_in(period - phase, Event::TimerIncrement);
+    }
+
+    fn increment_tima(&mut self, scheduler: &mut Scheduler<Event>) {
+        let (tima, overflow) = self.tima.overflowing_add(1);
+        self.tima = tima;
+        if overflow {
+            self.reload = Reload::Pending;
+            scheduler.cancel(Event::TimerReloadEnd);
+            scheduler.schedule_in(4, Event::TimerReload);
+        }
+    }
+
+    // Runs one of the timer events, `at` being the time it was scheduled
+    // for. Returns true when the timer interrupt should be requested.
+    pub fn handle(&mut self, event: Event, at: u64, scheduler: &mut Scheduler<Event>) -> bool {
+        match event {
+            Event::TimerIncrement => {
+                self.increment_tima(scheduler);
+                scheduler.schedule(at + (2 << self.divider_bit()), Event::TimerIncrement);
+                false
+            },
+            // TMA is loaded (and IF set) one M-cycle after the overflow.
+            Event::TimerReload => {
+                self.tima = self.tma;
+                self.reload = Reload::Reloaded;
+                scheduler.schedule_in(4, Event::TimerReloadEnd);
+                true
+            },
+            Event::TimerReloadEnd => {
+                self.reload = Reload::Idle;
+                false
+            },
+            _ => panic!("Not a timer event"),
+        }
+    }
+
+    pub fn read(&self, addr: usize, now: u64) -> u8 {
+        match addr {
+            DIV => (self.divider(now) >> 8) as u8,
+            TIMA => self.tima,
+            TMA => self.tma,
+            TAC => self.tac,
+            _ => panic!("Invalid timer register"),
+        }
+    }
+
+    pub fn write(&mut self, addr: usize, val: u8, scheduler: &mut Scheduler<Event>) {
+        // Resetting the counter or switching TAC can itself produce a falling
+        // edge on the signal and increment TIMA.
+        let now: u64 = scheduler.now();
+        let old: bool = self.signal(now);
+        match addr {
+            DIV => {
+                self.divider_base = 0;
+                self.epoch = now;
+            },
+            TIMA => match self.reload {
+                // Writing during the overflow cycle cancels the reload
+                Reload::Pending => {
+                    self.tima = val;
+                    self.reload = Reload::Idle;
+                    scheduler.cancel(Event::TimerReload);
+                },
+                // TMA wins on the cycle it is being copied
+                Reload::Reloaded => (),
+                Reload::Idle => self.tima = val,
+            },
+            TMA => {
+                self.tma = val;
+                if self.reload == Reload::Reloaded {
+                    self.tima = val;
+                }
+            },
+            TAC => self.tac = val & 0b111,
+            _ => panic!("Invalid timer register"),
+        }
+        if old && !self.signal(now) {
+            self.increment_tima(scheduler);
+        }
+        if addr == DIV || addr == TAC {
+            self.reschedule(scheduler);
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use std::fmt;
+
+use decoder::Instruction;
 use paste::paste;
 
 use crate::Console;
@@ -7,6 +10,37 @@ pub union Register {
     pub halves: [u8; 2]
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterSnapshot {
+    pub af: u16,
+    pub bc: u16,
+    pub de: u16,
+    pub hl: u16,
+    pub sp: u16,
+    pub pc: u16,
+}
+
+impl fmt::Display for RegisterSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AF:{:04X} BC:{:04X} DE:{:04X} HL:{:04X} SP:{:04X} PC:{:04X}",
+            self.af, self.bc, self.de, self.hl, self.sp, self.pc)
+    }
+}
+
+// State of a CPU hung by an illegal opcode. Only a reset gets it going again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lockup {
+    pub opcode: u8,
+    pub addr: u16,
+    pub regs: RegisterSnapshot,
+}
+
+impl fmt::Display for Lockup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CPU locked up by illegal opcode 0x{:02X} at 0x{:04X} ({})", self.opcode, self.addr, self.regs)
+    }
+}
+
 pub trait BitFlag {
     const VALUE: u8;
 
@@ -40,6 +74,8 @@ def_bitflag_type!(NO_CARRY, 1);
 def_bitflag_type!(LEFT, 0);
 def_bitflag_type!(RIGHT, 1);
 
+// Called before every instruction with its decoded form and address, and once
+// with no instruction before the first one is run.
 pub trait Hookable {
-    fn hook(&mut self, console: &mut Console, log: String, addr: u16);
+    fn hook(&mut self, console: &mut Console, instr: Option<Instruction>, addr: u16);
 }
\ No newline at end of file
@@ -1,2 +1,3 @@
 pub mod console;
-pub use console::{Console, types, debug_addr};
\ No newline at end of file
+pub use console::{Button, Console, Model, types, debug_addr};
+pub use ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
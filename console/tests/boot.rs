@@ -0,0 +1,108 @@
+mod common;
+
+use cartridge::Cartridge;
+use common::{cartridge_with_program, console_with_program, SPIN};
+use console::{Console, Model};
+use constants::{reg16, reg8, BGP, BOOT, DIV, DMA, IF, LCDC, NR52, OBP0, OBP1, P1, SC, TAC};
+
+const DMG_BOOT_ROM_SIZE: usize = 0x100;
+const CGB_BOOT_ROM_SIZE: usize = 0x900;
+const BOOT_ROM_BYTE: u8 = 0xB0;
+
+fn console_with_boot_rom(model: Model, size: usize) -> Console<'static> {
+    Console::init(cartridge_with_program(&SPIN, model.is_cgb()), model, Some(vec![BOOT_ROM_BYTE; size])).unwrap()
+}
+
+#[test]
+fn dmg_boot_rom_covers_the_first_256_bytes() {
+    let mut console = console_with_boot_rom(Model::Dmg, DMG_BOOT_ROM_SIZE);
+    assert_eq!(console.get_ip(), 0x0000);
+    assert_eq!(console.get_mem(0x0000), BOOT_ROM_BYTE);
+    assert_eq!(console.get_mem(0x00FF), BOOT_ROM_BYTE);
+    assert_eq!(console.get_mem(0x0100), SPIN[0]);
+    assert_eq!(console.get_mem(0x0200), 0x00);
+}
+
+#[test]
+fn cgb_boot_rom_leaves_the_header_visible() {
+    let mut console = console_with_boot_rom(Model::Cgb, CGB_BOOT_ROM_SIZE);
+    assert_eq!(console.get_mem(0x00FF), BOOT_ROM_BYTE);
+    // 0x0100 - 0x01FF is the cartridge, for the boot ROM to check the header
+    assert_eq!(console.get_mem(0x0100), SPIN[0]);
+    assert_eq!(console.get_mem(0x0143), 0x80);
+    assert_eq!(console.get_mem(0x01FF), 0x00);
+    assert_eq!(console.get_mem(0x0200), BOOT_ROM_BYTE);
+    assert_eq!(console.get_mem(0x08FF), BOOT_ROM_BYTE);
+    assert_eq!(console.get_mem(0x0900), 0x00);
+}
+
+#[test]
+fn writing_ff50_unmaps_the_boot_rom_for_good() {
+    for (model, size) in [(Model::Dmg, DMG_BOOT_ROM_SIZE), (Model::Cgb, CGB_BOOT_ROM_SIZE)] {
+        let mut console = console_with_boot_rom(model, size);
+        // Only non-zero writes count
+        console.set_mem(BOOT, 0x00);
+        assert_eq!(console.get_mem(0x0000), BOOT_ROM_BYTE);
+
+        console.set_mem(BOOT, 0x01);
+        assert_eq!(console.get_mem(0x0000), 0x00);
+        assert_eq!(console.get_mem(size - 1), 0x00);
+
+        console.set_mem(BOOT, 0x00);
+        console.set_mem(BOOT, 0xFF);
+        assert_eq!(console.get_mem(0x0000), 0x00);
+    }
+}
+
+// A, F, B, C, D, E, H, L as left by each boot ROM for a header checksum of
+// 0, then DIV, P1, SC, NR52 and DMA.
+// https://gbdev.io/pandocs/Power_Up_Sequence.html
+static POST_BOOT_STATE: [(Model, bool, [u8; 8], [u8; 5]); 9] = [
+    (Model::Dmg0, false, [0x01, 0x00, 0xFF, 0x13, 0x00, 0xC1, 0x84, 0x03], [0x18, 0xCF, 0x7E, 0xF1, 0xFF]),
+    (Model::Dmg, false, [0x01, 0x80, 0x00, 0x13, 0x00, 0xD8, 0x01, 0x4D], [0xAB, 0xCF, 0x7E, 0xF1, 0xFF]),
+    (Model::Mgb, false, [0xFF, 0x80, 0x00, 0x13, 0x00, 0xD8, 0x01, 0x4D], [0xAB, 0xCF, 0x7E, 0xF1, 0xFF]),
+    (Model::Sgb, false, [0x01, 0x00, 0x00, 0x14, 0x00, 0x00, 0xC0, 0x60], [0xD8, 0xFF, 0x7E, 0xF0, 0xFF]),
+    (Model::Sgb2, false, [0xFF, 0x00, 0x00, 0x14, 0x00, 0x00, 0xC0, 0x60], [0xD8, 0xFF, 0x7E, 0xF0, 0xFF]),
+    (Model::Cgb, true, [0x11, 0x80, 0x00, 0x00, 0xFF, 0x56, 0x00, 0x0D], [0x26, 0xCF, 0x7F, 0xF1, 0x00]),
+    (Model::Cgb, false, [0x11, 0x80, 0x00, 0x00, 0x00, 0x08, 0x00, 0x7C], [0x26, 0xCF, 0x7F, 0xF1, 0x00]),
+    (Model::Agb, true, [0x11, 0x00, 0x01, 0x00, 0xFF, 0x56, 0x00, 0x0D], [0x26, 0xCF, 0x7F, 0xF1, 0x00]),
+    (Model::Agb, false, [0x11, 0x00, 0x01, 0x00, 0x00, 0x08, 0x00, 0x7C], [0x26, 0xCF, 0x7F, 0xF1, 0x00]),
+];
+
+#[test]
+fn skipping_the_boot_rom_leaves_the_documented_state() {
+    for (model, cgb, regs, io) in POST_BOOT_STATE {
+        let mut console = console_with_program(&SPIN, model, cgb);
+        let what: String = format!("{model:?}, CGB header {cgb}");
+        let [a, f, b, c, d, e, h, l] = regs;
+        assert_eq!(console.get_r8(reg8::A), a, "A on {what}");
+        assert_eq!(console.get_flags(), f, "F on {what}");
+        assert_eq!(console.get_r16(reg16::BC), u16::from_be_bytes([b, c]), "BC on {what}");
+        assert_eq!(console.get_r16(reg16::DE), u16::from_be_bytes([d, e]), "DE on {what}");
+        assert_eq!(console.get_r16(reg16::HL), u16::from_be_bytes([h, l]), "HL on {what}");
+        assert_eq!(console.get_r16(reg16::SP), 0xFFFE, "SP on {what}");
+        assert_eq!(console.get_ip(), 0x0100, "PC on {what}");
+
+        for (addr, val) in [DIV, P1, SC, NR52, DMA].into_iter().zip(io) {
+            assert_eq!(console.peek_mem(addr), val, "0x{addr:04X} on {what}");
+        }
+        for (addr, val) in [(TAC, 0xF8), (IF, 0xE1), (LCDC, 0x91), (BGP, 0xFC), (OBP0, 0xFF), (OBP1, 0xFF)] {
+            assert_eq!(console.peek_mem(addr), val, "0x{addr:04X} on {what}");
+        }
+        // Audio, NR10 - NR51
+        for (addr, val) in [(0xFF10, 0x80), (0xFF11, 0xBF), (0xFF12, 0xF3), (0xFF24, 0x77), (0xFF25, 0xF3)] {
+            assert_eq!(console.peek_mem(addr), val, "0x{addr:04X} on {what}");
+        }
+    }
+}
+
+#[test]
+fn dmg_flags_follow_the_header_checksum() {
+    let mut rom: Vec<u8> = vec![0; 0x8000];
+    rom[0x014D] = 0x42;
+    for (model, f) in [(Model::Dmg, 0xB0), (Model::Mgb, 0xB0), (Model::Dmg0, 0x00), (Model::Cgb, 0x80)] {
+        let cartridge: Cartridge = Cartridge::from_bytes(rom.clone()).unwrap();
+        let console = Console::init(cartridge, model, None).unwrap();
+        assert_eq!(console.get_flags(), f, "{model:?}");
+    }
+}
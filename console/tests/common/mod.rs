@@ -0,0 +1,36 @@
+// Fixtures shared by the integration tests, pulled in with `mod common;`.
+// Not every test file uses all of them.
+#![allow(dead_code)]
+
+use cartridge::Cartridge;
+use console::{Console, Model};
+
+pub const ENTRY: usize = 0x0100;
+// JR -2, spins at the entry point
+pub const SPIN: [u8; 2] = [0x18, 0xFE];
+
+// ROM-only image with `program` placed at the entry point, CGB features
+// requested in the header when `cgb` is set.
+pub fn cartridge_with_program(program: &[u8], cgb: bool) -> Cartridge {
+    let mut rom: Vec<u8> = vec![0; 0x8000];
+    rom[ENTRY..ENTRY + program.len()].copy_from_slice(program);
+    if cgb {
+        rom[0x0143] = 0x80;
+    }
+    Cartridge::from_bytes(rom).unwrap()
+}
+
+// The same, past the boot ROM.
+pub fn console_with_program(program: &[u8], model: Model, cgb: bool) -> Console<'static> {
+    Console::init(cartridge_with_program(program, cgb), model, None).unwrap()
+}
+
+// DMG with nothing but the spin loop to run.
+pub fn idle_console() -> Console<'static> {
+    console_with_program(&SPIN, Model::Dmg, false)
+}
+
+// Same, on a CGB with a header asking for CGB features.
+pub fn idle_cgb_console() -> Console<'static> {
+    console_with_program(&SPIN, Model::Cgb, true)
+}
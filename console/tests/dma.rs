@@ -0,0 +1,109 @@
+mod common;
+
+use console::Console;
+use constants::{DMA, HRAM_BASE, LCDC, OAM_BASE, OAM_SIZE};
+
+// The LCD is off so that OAM stays readable.
+fn lcd_off_console() -> Console<'static> {
+    let mut console = common::idle_console();
+    console.set_mem(LCDC, 0x00);
+    console
+}
+
+fn fill(console: &mut Console, base: usize, f: impl Fn(usize) -> u8) {
+    for i in 0..OAM_SIZE {
+        console.set_mem(base + i, f(i));
+    }
+}
+
+fn oam(console: &Console, i: usize) -> u8 {
+    console.peek_mem(OAM_BASE + i)
+}
+
+#[test]
+fn copies_one_byte_per_mcycle_after_a_start_up_mcycle() {
+    let mut console = lcd_off_console();
+    fill(&mut console, 0xC000, |i| i as u8 + 1);
+    console.set_mem(DMA, 0xC0);
+
+    console.mcycle();
+    assert_eq!(oam(&console, 0), 0);
+    console.mcycle();
+    assert_eq!(oam(&console, 0), 1);
+    assert_eq!(oam(&console, 1), 0);
+    for _ in 0..OAM_SIZE - 1 {
+        console.mcycle();
+    }
+    for i in 0..OAM_SIZE {
+        assert_eq!(oam(&console, i), i as u8 + 1, "{i}");
+    }
+    assert_eq!(console.peek_mem(DMA), 0xC0);
+}
+
+#[test]
+fn cpu_only_sees_the_0xff00_page_during_the_transfer() {
+    let mut console = lcd_off_console();
+    console.set_mem(0xC000, 0x12);
+    console.set_mem(HRAM_BASE, 0x34);
+    console.set_mem(DMA, 0xC0);
+
+    // Start up M-cycle
+    assert_eq!(console.get_mem(0xC000), 0x12);
+    assert_eq!(console.get_mem(0xC000), 0xFF);
+    assert_eq!(console.get_mem(0x0100), 0xFF);
+    assert_eq!(console.get_mem(HRAM_BASE), 0x34);
+    console.set_mem(0xC000, 0x56);
+    console.set_mem(HRAM_BASE, 0x78);
+    // The last of the 160 blocked M-cycles, then free again
+    for _ in 0..OAM_SIZE - 6 {
+        console.mcycle();
+    }
+    assert_eq!(console.get_mem(0xC000), 0xFF);
+    assert_eq!(console.get_mem(0xC000), 0x12);
+    assert_eq!(console.get_mem(HRAM_BASE), 0x78);
+}
+
+#[test]
+fn restart_takes_over_after_its_start_up_mcycle() {
+    let mut console = lcd_off_console();
+    fill(&mut console, 0xC000, |i| i as u8);
+    fill(&mut console, 0xC100, |i| !(i as u8));
+    console.set_mem(DMA, 0xC0);
+    for _ in 0..10 {
+        console.mcycle();
+    }
+    console.set_mem(DMA, 0xC1);
+
+    // The first transfer still runs, the CPU stays locked out
+    assert_eq!(console.get_mem(0xC000), 0xFF);
+    assert_eq!(oam(&console, 10), 10);
+    console.mcycle();
+    assert_eq!(oam(&console, 0), 0xFF);
+    assert_eq!(oam(&console, 11), 0);
+    for _ in 0..OAM_SIZE - 1 {
+        console.mcycle();
+    }
+    for i in 0..OAM_SIZE {
+        assert_eq!(oam(&console, i), !(i as u8), "{i}");
+    }
+    assert_eq!(console.get_mem(0xC000), 0x00);
+}
+
+#[test]
+fn sources_above_wram_read_echo_ram() {
+    let mut console = lcd_off_console();
+    fill(&mut console, 0xC000, |i| i as u8 ^ 0x55);
+    fill(&mut console, 0xDE00, |i| i as u8 ^ 0xAA);
+
+    console.set_mem(DMA, 0xE0);
+    for _ in 0..OAM_SIZE + 1 {
+        console.mcycle();
+    }
+    assert_eq!(oam(&console, 7), 7 ^ 0x55);
+
+    console.set_mem(DMA, 0xFE);
+    for _ in 0..OAM_SIZE + 1 {
+        console.mcycle();
+    }
+    assert_eq!(oam(&console, 7), 7 ^ 0xAA);
+}
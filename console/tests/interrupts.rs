@@ -0,0 +1,218 @@
+mod common;
+
+use common::console_with_program;
+use console::{types::Lockup, Console, Model};
+use constants::{intr, reg16, reg8, IE, IF};
+
+fn request(console: &mut Console, ie: u8, if_: u8) {
+    console.set_mem(IE, ie);
+    console.set_mem(IF, if_);
+}
+
+#[test]
+fn every_vector_is_reached() {
+    for mask in [intr::VBLANK, intr::LCD, intr::TIMER, intr::SERIAL, intr::JOYPAD] {
+        let mut console = console_with_program(&[0x00], Model::Dmg, false);
+        request(&mut console, intr::ALL, mask);
+        console.set_ime(1);
+
+        console.step();
+        assert_eq!(console.get_ip(), intr::get_jump_vector(mask));
+        assert_eq!(console.peek_mem(IF) & mask, 0);
+        assert_eq!(console.get_r16(reg16::SP), 0xFFFC);
+        assert_eq!(console.peek_mem(0xFFFC), 0x00);
+        assert_eq!(console.peek_mem(0xFFFD), 0x01);
+    }
+}
+
+#[test]
+fn lowest_bit_has_priority() {
+    let mut console = console_with_program(&[0x00], Model::Dmg, false);
+    request(&mut console, intr::ALL, intr::TIMER | intr::SERIAL | intr::JOYPAD);
+    console.set_ime(1);
+
+    console.step();
+    assert_eq!(console.get_ip(), intr::get_jump_vector(intr::TIMER));
+    assert_eq!(console.peek_mem(IF) & intr::ALL, intr::SERIAL | intr::JOYPAD);
+}
+
+#[test]
+fn disabled_interrupts_are_ignored() {
+    let mut console = console_with_program(&[0x00], Model::Dmg, false);
+    request(&mut console, intr::LCD, intr::VBLANK);
+    console.set_ime(1);
+    console.step();
+    assert_eq!(console.get_ip(), 0x0101);
+
+    let mut console = console_with_program(&[0x00], Model::Dmg, false);
+    request(&mut console, intr::ALL, intr::VBLANK);
+    console.step();
+    assert_eq!(console.get_ip(), 0x0101);
+}
+
+#[test]
+fn dispatch_takes_five_mcycles() {
+    let mut console = console_with_program(&[0x00], Model::Dmg, false);
+    request(&mut console, intr::ALL, intr::VBLANK);
+    console.set_ime(1);
+
+    let start: u64 = console.cycles();
+    console.step();
+    assert_eq!(console.cycles() - start, 5 * 4);
+}
+
+#[test]
+fn ei_is_delayed_by_one_instruction() {
+    // EI; NOP; NOP
+    let mut console = console_with_program(&[0xFB, 0x00, 0x00], Model::Dmg, false);
+    request(&mut console, intr::ALL, intr::VBLANK);
+
+    console.step();
+    assert_eq!(console.get_ip(), 0x0101);
+    console.step();
+    assert_eq!(console.get_ip(), 0x0102);
+    console.step();
+    assert_eq!(console.get_ip(), intr::get_jump_vector(intr::VBLANK));
+    assert_eq!(console.peek_mem(0xFFFC), 0x02);
+}
+
+#[test]
+fn di_right_after_ei_keeps_interrupts_disabled() {
+    // EI; DI; NOP
+    let mut console = console_with_program(&[0xFB, 0xF3, 0x00], Model::Dmg, false);
+    request(&mut console, intr::ALL, intr::VBLANK);
+
+    for _ in 0..3 {
+        console.step();
+    }
+    assert_eq!(console.get_ip(), 0x0103);
+}
+
+#[test]
+fn ie_overwritten_by_push_cancels_dispatch() {
+    // LD SP, 0x0000; NOP. The upper byte of PC (0x01) lands in IE.
+    let mut console = console_with_program(&[0x31, 0x00, 0x00, 0x00], Model::Dmg, false);
+    request(&mut console, intr::TIMER, intr::TIMER);
+    console.step();
+    console.set_ime(1);
+
+    console.step();
+    assert_eq!(console.get_ip(), 0x0000);
+    assert_eq!(console.peek_mem(IE), 0x01);
+    assert_eq!(console.peek_mem(IF) & intr::TIMER, intr::TIMER);
+}
+
+#[test]
+fn ie_overwritten_by_push_can_pick_another_vector() {
+    // LD SP, 0x0000; NOP. The upper byte of PC (0x01) lands in IE.
+    let mut console = console_with_program(&[0x31, 0x00, 0x00, 0x00], Model::Dmg, false);
+    request(&mut console, intr::TIMER, intr::TIMER | intr::VBLANK);
+    console.step();
+    console.set_ime(1);
+
+    console.step();
+    assert_eq!(console.get_ip(), intr::get_jump_vector(intr::VBLANK));
+    assert_eq!(console.peek_mem(IF) & intr::ALL, intr::TIMER);
+}
+
+#[test]
+fn halt_waits_for_an_interrupt_and_services_it() {
+    // HALT; NOP
+    let mut console = console_with_program(&[0x76, 0x00], Model::Dmg, false);
+    console.set_mem(IE, intr::TIMER);
+    console.set_ime(1);
+
+    console.step();
+    assert!(console.is_halted());
+    for _ in 0..10 {
+        console.step();
+    }
+    assert_eq!(console.get_ip(), 0x0101);
+
+    console.set_mem(IF, intr::TIMER);
+    console.step();
+    assert!(!console.is_halted());
+    assert_eq!(console.get_ip(), intr::get_jump_vector(intr::TIMER));
+    assert_eq!(console.peek_mem(0xFFFC), 0x01);
+}
+
+#[test]
+fn halt_with_ime_clear_wakes_without_servicing() {
+    // HALT; NOP
+    let mut console = console_with_program(&[0x76, 0x00], Model::Dmg, false);
+    console.set_mem(IE, intr::TIMER);
+
+    console.step();
+    assert!(console.is_halted());
+    console.set_mem(IF, intr::TIMER);
+    console.step();
+    assert!(!console.is_halted());
+    assert_eq!(console.get_ip(), 0x0102);
+    assert_eq!(console.peek_mem(IF) & intr::TIMER, intr::TIMER);
+}
+
+#[test]
+fn ei_then_halt_with_an_interrupt_pending_returns_to_the_halt() {
+    // EI; HALT; INC A
+    let mut console = console_with_program(&[0xFB, 0x76, 0x3C], Model::Dmg, false);
+    request(&mut console, intr::TIMER, intr::TIMER);
+    let a: u8 = console.get_r8(reg8::A);
+
+    console.step();
+    console.step();
+    assert!(!console.is_halted());
+    console.step();
+    assert_eq!(console.get_ip(), intr::get_jump_vector(intr::TIMER));
+    assert_eq!(console.peek_mem(0xFFFD), 0x01);
+    assert_eq!(console.peek_mem(0xFFFC), 0x01);
+    assert_eq!(console.get_r8(reg8::A), a);
+
+    // Back from the handler, HALT runs again and sleeps this time
+    console.set_ime(0);
+    console.set_ip(0x0101);
+    console.step();
+    assert!(console.is_halted());
+}
+
+#[test]
+fn halt_bug_reads_the_next_byte_twice() {
+    // HALT; INC A
+    let mut console = console_with_program(&[0x76, 0x3C], Model::Dmg, false);
+    request(&mut console, intr::TIMER, intr::TIMER);
+    let a: u8 = console.get_r8(reg8::A);
+
+    console.step();
+    assert!(!console.is_halted());
+    console.step();
+    assert_eq!(console.get_ip(), 0x0101);
+    console.step();
+    assert_eq!(console.get_ip(), 0x0102);
+    assert_eq!(console.get_r8(reg8::A), a.wrapping_add(2));
+}
+
+#[test]
+fn illegal_opcodes_lock_up_the_cpu_for_good() {
+    for opcode in [0xD3u8, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD] {
+        // NOP; <illegal>
+        let mut console = console_with_program(&[0x00, opcode], Model::Dmg, false);
+        console.step();
+        assert!(console.lockup().is_none());
+        console.step();
+        let lockup: &Lockup = console.lockup().expect("no lockup");
+        assert_eq!((lockup.opcode, lockup.addr), (opcode, 0x0101), "opcode 0x{:02X}", opcode);
+        assert!(lockup.to_string().contains(&format!("0x{:02X} at 0x0101", opcode)));
+
+        // Time goes on, but not even an interrupt gets the CPU going again
+        let pc: u16 = console.get_ip();
+        console.set_ime(1);
+        request(&mut console, intr::TIMER, intr::TIMER);
+        let start: u64 = console.cycles();
+        for _ in 0..10 {
+            console.step();
+        }
+        assert!(console.cycles() > start);
+        assert_eq!(console.get_ip(), pc);
+        assert_ne!(console.peek_mem(IF) & intr::TIMER, 0);
+        assert_eq!(console.lockup().unwrap().opcode, opcode);
+    }
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use common::{console_with_program, ENTRY};
+use console::{Button, Model};
+use constants::{intr, reg8, BGP, DIV, IE, IF, KEY1, LY, P1};
+
+const SELECT_DPAD: u8 = 0x20;
+const SELECT_BUTTONS: u8 = 0x10;
+
+#[test]
+fn select_lines_pick_the_group_read() {
+    let mut console = console_with_program(&[0x00], Model::Dmg, false);
+    console.set_button(Button::A, true);
+    console.set_button(Button::Down, true);
+
+    console.set_mem(P1, SELECT_BUTTONS);
+    assert_eq!(console.get_mem(P1), 0xC0 | SELECT_BUTTONS | 0b1110);
+    console.set_mem(P1, SELECT_DPAD);
+    assert_eq!(console.get_mem(P1), 0xC0 | SELECT_DPAD | 0b0111);
+    // Both groups pull the lines low together
+    console.set_mem(P1, 0x00);
+    assert_eq!(console.get_mem(P1), 0xC0 | 0b0110);
+    console.set_mem(P1, SELECT_DPAD | SELECT_BUTTONS);
+    assert_eq!(console.get_mem(P1), 0xFF);
+}
+
+#[test]
+fn only_selected_lines_going_low_interrupt() {
+    let mut console = console_with_program(&[0x00], Model::Dmg, false);
+    console.set_mem(P1, SELECT_BUTTONS);
+    console.set_mem(IF, 0x00);
+    console.set_button(Button::Down, true);
+    assert_eq!(console.peek_mem(IF) & intr::JOYPAD, 0);
+
+    console.set_button(Button::Start, true);
+    assert_ne!(console.peek_mem(IF) & intr::JOYPAD, 0);
+
+    // Selecting the D-pad with Down held is a falling edge as well, as long
+    // as the line was not low already
+    console.set_button(Button::Start, false);
+    console.set_mem(IF, 0x00);
+    console.set_mem(P1, SELECT_DPAD);
+    assert_ne!(console.peek_mem(IF) & intr::JOYPAD, 0);
+}
+
+#[test]
+fn stop_sleeps_until_a_selected_button_is_pressed() {
+    // STOP; INC A
+    let mut console = console_with_program(&[0x10, 0x00, 0x3C], Model::Dmg, false);
+    console.set_mem(BGP, 0xFF);
+    // Two frames without running the program
+    for _ in 0..2 * 70224 / 4 {
+        console.mcycle();
+    }
+    assert!(console.framebuffer().iter().all(|&shade| shade == 3));
+    console.set_mem(P1, SELECT_BUTTONS);
+    console.set_mem(IE, 0x00);
+    let a: u8 = console.get_r8(reg8::A);
+
+    console.step();
+    assert!(console.is_stopped());
+    assert!(console.framebuffer().iter().all(|&shade| shade == 0));
+
+    // Time goes by with everything else frozen
+    let ly: u8 = console.peek_mem(LY);
+    let start: u64 = console.cycles();
+    console.run_frame();
+    console.run_frame();
+    assert!(console.cycles() - start >= 2 * 70224);
+    assert_eq!(console.peek_mem(DIV), 0);
+    assert_eq!(console.peek_mem(LY), ly);
+    assert!(console.framebuffer().iter().all(|&shade| shade == 0));
+
+    // Not selected, still asleep
+    console.set_button(Button::Up, true);
+    console.step();
+    assert!(console.is_stopped());
+
+    console.set_button(Button::A, true);
+    console.step();
+    assert!(!console.is_stopped());
+    assert_eq!(console.get_r8(reg8::A), a.wrapping_add(1));
+}
+
+#[test]
+fn stop_switches_speed_once_key1_is_armed() {
+    // STOP; NOP
+    let mut console = console_with_program(&[0x10, 0x00, 0x00], Model::Cgb, true);
+    console.set_mem(IE, 0x00);
+    assert_eq!(console.peek_mem(KEY1), 0x7E);
+    console.set_mem(KEY1, 0x01);
+    assert_eq!(console.peek_mem(KEY1), 0x7F);
+
+    console.step();
+    assert!(!console.is_stopped());
+    assert!(console.is_double_speed());
+    assert_eq!(console.peek_mem(KEY1), 0xFE);
+
+    // The CPU stalls for a while before going on
+    let pc: u16 = console.get_ip();
+    console.step();
+    assert_eq!(console.get_ip(), pc);
+
+    // And back with a second STOP once the first stall is over
+    let mut console = console_with_program(&[0x10, 0x00, 0x10, 0x00, 0x00], Model::Cgb, true);
+    console.set_mem(IE, 0x00);
+    console.set_mem(KEY1, 0x01);
+    console.step();
+    console.set_mem(KEY1, 0x01);
+    while console.get_ip() == ENTRY as u16 + 2 {
+        console.step();
+    }
+    assert!(!console.is_double_speed());
+    assert_eq!(console.peek_mem(KEY1), 0x7E);
+}
+
+#[test]
+fn key1_is_absent_outside_of_cgb_mode() {
+    // A DMG, then a CGB running a DMG title; STOP; NOP
+    for model in [Model::Dmg, Model::Cgb] {
+        let mut console = console_with_program(&[0x10, 0x00, 0x00], model, false);
+        console.set_mem(IE, 0x00);
+        console.set_mem(KEY1, 0x01);
+        assert_eq!(console.peek_mem(KEY1), 0xFF);
+        console.step();
+        assert!(console.is_stopped());
+        assert!(!console.is_double_speed());
+    }
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use cartridge::Cartridge;
+use common::{idle_cgb_console, idle_console};
+use console::{Console, Model};
+use constants::{IF, NR52, SC, STAT, SVBK, TAC};
+
+const ROM_BANK_SIZE: usize = 0x4000;
+
+#[test]
+fn echo_ram_mirrors_wram_both_ways() {
+    let mut console = idle_console();
+    for (wram, echo) in [(0xC000, 0xE000), (0xC123, 0xE123), (0xDDFF, 0xFDFF)] {
+        console.set_mem(wram, 0x42);
+        assert_eq!(console.get_mem(echo), 0x42, "0x{echo:04X}");
+        console.set_mem(echo, 0x24);
+        assert_eq!(console.get_mem(wram), 0x24, "0x{wram:04X}");
+    }
+}
+
+#[test]
+fn echo_ram_follows_the_cgb_wram_bank() {
+    let mut console = idle_cgb_console();
+    console.set_mem(SVBK, 3);
+    console.set_mem(0xD010, 0x33);
+    console.set_mem(SVBK, 5);
+    console.set_mem(0xF010, 0x55);
+    assert_eq!(console.get_mem(0xD010), 0x55);
+    console.set_mem(SVBK, 3);
+    assert_eq!(console.get_mem(0xF010), 0x33);
+}
+
+#[test]
+fn unusable_area_ignores_writes() {
+    let mut console = idle_console();
+    for addr in [0xFEA0, 0xFEC5, 0xFEFF] {
+        console.set_mem(addr, 0x5A);
+        assert_eq!(console.peek_mem(addr), 0x00, "0x{addr:04X}");
+    }
+    // Nothing leaks into OAM or the IO registers around it
+    assert_eq!(console.peek_mem(0xFE9F), 0x00);
+}
+
+#[test]
+fn unused_io_bits_read_as_1() {
+    let mut console = idle_console();
+    for (addr, mask) in [(SC, 0x7E), (TAC, 0xF8), (IF, 0xE0), (STAT, 0x80), (NR52, 0x70)] {
+        console.set_mem(addr, 0x00);
+        assert_eq!(console.peek_mem(addr) & mask, mask, "0x{addr:04X}");
+    }
+    // Registers that are not there at all
+    for addr in [0xFF03, 0xFF08, 0xFF15, 0xFF4C, 0xFF7F] {
+        console.set_mem(addr, 0x00);
+        assert_eq!(console.peek_mem(addr), 0xFF, "0x{addr:04X}");
+    }
+}
+
+#[test]
+fn rom_writes_reach_the_mapper() {
+    // MBC1 with 4 banks, each filled with its number
+    let mut rom: Vec<u8> = (0..4 * ROM_BANK_SIZE).map(|i| (i / ROM_BANK_SIZE) as u8).collect();
+    rom[0x0147] = 0x01;
+    rom[0x0148] = 0x01;
+    let cartridge: Cartridge = Cartridge::from_bytes(rom).unwrap();
+    let mut console = Console::init(cartridge, Model::Dmg, None).unwrap();
+    assert_eq!(console.get_mem(0x4000), 1);
+
+    console.set_mem(0x2000, 0x03);
+    assert_eq!(console.get_mem(0x4000), 3);
+    assert_eq!(console.get_mem(0x7FFF), 3);
+    // The written addresses still read the ROM
+    assert_eq!(console.get_mem(0x2000), 0);
+    console.set_mem(0x0150, 0xAA);
+    assert_eq!(console.get_mem(0x0150), 0);
+}
@@ -0,0 +1,730 @@
+mod common;
+
+use common::{idle_cgb_console, idle_console};
+use console::{Console, SCREEN_HEIGHT, SCREEN_WIDTH};
+use constants::{intr, BCPD, BCPS, BGP, IF, LCDC, LY, LYC, OAM_BASE, OBP0, OBP1, OCPD, OCPS, SCX, SCY, STAT, SVBK, VBK, WX, WY};
+
+fn shade(console: &Console, x: usize, y: usize) -> u8 {
+    console.framebuffer()[y * SCREEN_WIDTH + x]
+}
+
+// Tile 1 is solid color 3, tile 2 has color 1 in its left half only. The
+// map at 0x9800 is a checkerboard of tiles 0 and 1. Leaves the LCD off so
+// that VRAM can be written.
+fn load_tiles(console: &mut Console) {
+    console.set_mem(LCDC, 0x11);
+    for row in 0..8 {
+        console.set_mem(0x8010 + row * 2, 0xFF);
+        console.set_mem(0x8011 + row * 2, 0xFF);
+        console.set_mem(0x8020 + row * 2, 0xF0);
+    }
+    for i in 0..32 * 32 {
+        let tile: u8 = ((i % 32 + i / 32) % 2) as u8;
+        console.set_mem(0x9800 + i, tile);
+    }
+}
+
+#[test]
+fn renders_the_background_headlessly() {
+    let mut console = idle_console();
+    load_tiles(&mut console);
+    console.set_mem(BGP, 0b11_10_01_00);
+    console.set_mem(LCDC, 0x91);
+    console.run_frame();
+    console.run_frame();
+
+    for (x, y) in [(0, 0), (7, 7), (8, 8), (159, 143)] {
+        assert_eq!(shade(&console, x, y), 0, "({x}, {y})");
+    }
+    for (x, y) in [(8, 0), (15, 7), (0, 8), (144, 143)] {
+        assert_eq!(shade(&console, x, y), 3, "({x}, {y})");
+    }
+    let rgba: &[u8] = &console.rgba()[8 * 4..8 * 4 + 4];
+    assert_eq!(rgba, &[0x00, 0x00, 0x00, 0xFF]);
+}
+
+#[test]
+fn scrolling_and_palette_are_applied() {
+    let mut console = idle_console();
+    load_tiles(&mut console);
+    // Inverted palette, scrolled by 4 pixels right and one tile down
+    console.set_mem(BGP, 0b00_01_10_11);
+    console.set_mem(SCX, 4);
+    console.set_mem(SCY, 8);
+    console.set_mem(LCDC, 0x91);
+    console.run_frame();
+    console.run_frame();
+
+    // Map row 1 starts with tile 1, which ends at x = 3 on screen
+    assert_eq!(shade(&console, 0, 0), 0);
+    assert_eq!(shade(&console, 3, 0), 0);
+    assert_eq!(shade(&console, 4, 0), 3);
+    assert_eq!(shade(&console, 12, 0), 0);
+}
+
+#[test]
+fn signed_tile_data_addressing() {
+    let mut console = idle_console();
+    console.set_mem(LCDC, 0x01);
+    // Tile 0 in the 0x8800 area is at 0x9000
+    for row in 0..8 {
+        console.set_mem(0x9000 + row * 2, 0xF0);
+    }
+    console.set_mem(BGP, 0b11_10_01_00);
+    console.set_mem(LCDC, 0x81);
+    console.run_frame();
+    console.run_frame();
+
+    assert_eq!(shade(&console, 0, 0), 1);
+    assert_eq!(shade(&console, 4, 0), 0);
+    assert_eq!(shade(&console, 8, SCREEN_HEIGHT - 1), 1);
+}
+
+#[test]
+fn frames_complete_at_the_console_refresh_rate() {
+    let mut console = idle_console();
+    console.run_frame();
+    let start: u64 = console.cycles();
+    console.run_frame();
+    let cycles: u64 = console.cycles() - start;
+    // Steps are whole instructions, so the frame can end a few cycles late
+    assert!((70224..70224 + 12).contains(&cycles), "{cycles}");
+}
+
+// Restarts the LCD so that the next M-cycle is the first one of line 0.
+fn restart_lcd(console: &mut Console) {
+    console.set_mem(LCDC, 0x11);
+    console.set_mem(LCDC, 0x91);
+}
+
+fn advance_dots(console: &mut Console, dots: u64) {
+    for _ in 0..dots / 4 {
+        console.mcycle();
+    }
+}
+
+fn mode(console: &Console) -> u8 {
+    console.peek_mem(STAT) & 0x03
+}
+
+#[test]
+fn modes_follow_each_other_within_a_line() {
+    let mut console = idle_console();
+    restart_lcd(&mut console);
+    // First line: 80 dots of mode 0 instead of the OAM scan
+    advance_dots(&mut console, 456 + 4);
+    assert_eq!(console.peek_mem(LY), 1);
+    assert_eq!(mode(&console), 2);
+    advance_dots(&mut console, 80);
+    assert_eq!(mode(&console), 3);
+    advance_dots(&mut console, 172);
+    assert_eq!(mode(&console), 0);
+    advance_dots(&mut console, 200);
+    assert_eq!(console.peek_mem(LY), 2);
+    assert_eq!(mode(&console), 2);
+}
+
+#[test]
+fn vblank_starts_at_line_144() {
+    let mut console = idle_console();
+    restart_lcd(&mut console);
+    console.set_mem(IF, 0);
+    advance_dots(&mut console, 144 * 456 - 8);
+    assert_eq!(console.peek_mem(LY), 143);
+    assert_eq!(console.peek_mem(IF) & intr::VBLANK, 0);
+
+    advance_dots(&mut console, 8);
+    assert_eq!(console.peek_mem(LY), 144);
+    assert_eq!(mode(&console), 1);
+    assert_ne!(console.peek_mem(IF) & intr::VBLANK, 0);
+
+    advance_dots(&mut console, 10 * 456);
+    assert_eq!(console.peek_mem(LY), 0);
+    assert_eq!(mode(&console), 2);
+}
+
+#[test]
+fn lyc_match_raises_stat() {
+    let mut console = idle_console();
+    restart_lcd(&mut console);
+    console.set_mem(LYC, 3);
+    console.set_mem(STAT, 0x40);
+    console.set_mem(IF, 0);
+
+    // The three writes above took 12 dots
+    advance_dots(&mut console, 3 * 456 - 12 - 8);
+    assert_eq!(console.peek_mem(STAT) & 0x04, 0);
+    assert_eq!(console.peek_mem(IF) & intr::LCD, 0);
+    advance_dots(&mut console, 8);
+    assert_eq!(console.peek_mem(STAT) & 0x04, 0x04);
+    assert_ne!(console.peek_mem(IF) & intr::LCD, 0);
+}
+
+#[test]
+fn stat_line_blocks_back_to_back_sources() {
+    let mut console = idle_console();
+    restart_lcd(&mut console);
+    // HBlank of line 2 runs straight into the LYC match of line 3, the line
+    // stays high and only the first source gets an interrupt.
+    console.set_mem(LYC, 3);
+    console.set_mem(STAT, 0x48);
+    advance_dots(&mut console, 2 * 456 + 80 + 172 + 8);
+    assert_eq!(mode(&console), 0);
+    console.set_mem(IF, 0);
+
+    advance_dots(&mut console, 456);
+    assert_eq!(console.peek_mem(LY), 3);
+    assert_eq!(console.peek_mem(IF) & intr::LCD, 0);
+}
+
+#[test]
+fn lcd_off_resets_ly_and_mode() {
+    let mut console = idle_console();
+    restart_lcd(&mut console);
+    advance_dots(&mut console, 10 * 456);
+    console.set_mem(LCDC, 0x11);
+    assert_eq!(console.peek_mem(LY), 0);
+    assert_eq!(mode(&console), 0);
+    advance_dots(&mut console, 10 * 456);
+    assert_eq!(console.peek_mem(LY), 0);
+}
+
+// Window map at 0x9C00 filled with tile 2, tile 1 from row `solid_row` on.
+fn load_window(console: &mut Console, solid_row: usize) {
+    console.set_mem(LCDC, 0x11);
+    for i in 0..32 * 32 {
+        let tile: u8 = if i / 32 >= solid_row { 1 } else { 2 };
+        console.set_mem(0x9C00 + i, tile);
+    }
+}
+
+#[test]
+fn window_covers_the_background_from_wx_wy() {
+    let mut console = idle_console();
+    load_tiles(&mut console);
+    load_window(&mut console, 32);
+    console.set_mem(BGP, 0b11_10_01_00);
+    console.set_mem(WY, 16);
+    console.set_mem(WX, 40 + 7);
+    console.set_mem(LCDC, 0xF1);
+    console.run_frame();
+    console.run_frame();
+
+    // Background above and left of the window
+    assert_eq!(shade(&console, 48, 15), 3);
+    assert_eq!(shade(&console, 39, 16), 0);
+    assert_eq!(shade(&console, 40, 16), 1);
+    assert_eq!(shade(&console, 47, 16), 0);
+    assert_eq!(shade(&console, 48, 143), 1);
+}
+
+#[test]
+fn window_line_counter_skips_lines_without_window() {
+    let mut console = idle_console();
+    load_tiles(&mut console);
+    load_window(&mut console, 1);
+    console.set_mem(BGP, 0b11_10_01_00);
+    console.set_mem(WY, 0);
+    console.set_mem(WX, 7);
+    restart_lcd(&mut console);
+    console.set_mem(LCDC, 0xF1);
+    // Window off for lines 4 - 11, switched during the HBlank before
+    advance_dots(&mut console, 4 * 456 - 24);
+    console.set_mem(LCDC, 0xD1);
+    advance_dots(&mut console, 8 * 456);
+    console.set_mem(LCDC, 0xF1);
+    console.run_frame();
+
+    assert_eq!(shade(&console, 0, 3), 1);
+    assert_eq!(shade(&console, 0, 4), 0);
+    // Line 12 shows window line 4, still in the first row of tiles
+    assert_eq!(shade(&console, 0, 12), 1);
+    assert_eq!(shade(&console, 0, 15), 1);
+    assert_eq!(shade(&console, 0, 16), 3);
+}
+
+#[test]
+fn window_left_of_the_screen_is_cut() {
+    let mut console = idle_console();
+    load_tiles(&mut console);
+    load_window(&mut console, 32);
+    console.set_mem(BGP, 0b11_10_01_00);
+    console.set_mem(WY, 0);
+    console.set_mem(WX, 3);
+    console.set_mem(LCDC, 0xF1);
+    console.run_frame();
+    console.run_frame();
+
+    // The first 4 window pixels are dropped
+    assert_eq!(shade(&console, 0, 0), 0);
+    assert_eq!(shade(&console, 4, 0), 1);
+    assert_eq!(shade(&console, 8, 0), 0);
+    assert_eq!(shade(&console, 12, 0), 1);
+}
+
+#[test]
+fn window_at_the_left_edge_ignores_the_fine_scroll() {
+    let mut console = idle_console();
+    load_tiles(&mut console);
+    load_window(&mut console, 32);
+    console.set_mem(BGP, 0b11_10_01_00);
+    console.set_mem(SCX, 3);
+    console.set_mem(WY, 0);
+    console.set_mem(WX, 7);
+    console.set_mem(LCDC, 0xF1);
+    console.run_frame();
+    console.run_frame();
+
+    // The SCX discard applies to the background only
+    assert_eq!(shade(&console, 0, 0), 1);
+    assert_eq!(shade(&console, 3, 0), 1);
+    assert_eq!(shade(&console, 4, 0), 0);
+    assert_eq!(shade(&console, 8, 0), 1);
+    assert_eq!(shade(&console, 159, 143), 0);
+}
+
+#[test]
+fn mode_3_lasts_longer_with_scroll_and_window() {
+    let mut console = idle_console();
+    console.set_mem(SCX, 7);
+    restart_lcd(&mut console);
+    advance_dots(&mut console, 456 + 4 + 80 + 172);
+    assert_eq!(mode(&console), 3);
+    advance_dots(&mut console, 4);
+    assert_eq!(mode(&console), 0);
+
+    // The window restarts the fetcher, 6 more dots
+    console.set_mem(WY, 0);
+    console.set_mem(WX, 80 + 7);
+    console.set_mem(SCX, 0);
+    restart_lcd(&mut console);
+    console.set_mem(LCDC, 0xB1);
+    advance_dots(&mut console, 456 + 80 + 172);
+    assert_eq!(mode(&console), 3);
+    advance_dots(&mut console, 4);
+    assert_eq!(mode(&console), 0);
+}
+
+#[test]
+fn writes_during_mode_3_apply_from_the_next_pixels_on() {
+    // Inverting the palette around pixel 80 of line 1
+    let mut console = idle_console();
+    load_tiles(&mut console);
+    console.set_mem(BGP, 0b11_10_01_00);
+    restart_lcd(&mut console);
+    advance_dots(&mut console, 456 + 80 + 80);
+    assert_eq!(mode(&console), 3);
+    console.set_mem(BGP, 0b00_01_10_11);
+    advance_dots(&mut console, 200);
+    assert_eq!(shade(&console, 0, 1), 0);
+    assert_eq!(shade(&console, 8, 1), 3);
+    assert_eq!(shade(&console, 144, 1), 3);
+    assert_eq!(shade(&console, 152, 1), 0);
+
+    // Scrolling by a tile around the same pixel, the tiles already fetched
+    // stay where they are
+    let mut console = idle_console();
+    load_tiles(&mut console);
+    console.set_mem(BGP, 0b11_10_01_00);
+    restart_lcd(&mut console);
+    advance_dots(&mut console, 456 + 80 + 80);
+    console.set_mem(SCX, 8);
+    advance_dots(&mut console, 200);
+    assert_eq!(shade(&console, 0, 1), 0);
+    assert_eq!(shade(&console, 8, 1), 3);
+    assert_eq!(shade(&console, 144, 1), 3);
+    assert_eq!(shade(&console, 152, 1), 0);
+    // The next line is scrolled all the way
+    advance_dots(&mut console, 456);
+    assert_eq!(shade(&console, 0, 2), 3);
+}
+
+// Writes OAM entry `idx` with the LCD off, left off.
+fn put_object(console: &mut Console, idx: usize, y: u8, x: u8, tile: u8, attrs: u8) {
+    console.set_mem(LCDC, 0x13);
+    for (i, val) in [y, x, tile, attrs].into_iter().enumerate() {
+        console.set_mem(OAM_BASE + idx * 4 + i, val);
+    }
+}
+
+// Tiles 1 and 2 as in `load_tiles`, tile 3 all color 1 and tile 4 with
+// only its top row set to color 3. The background map is left to tile 0.
+fn load_object_tiles(console: &mut Console) {
+    console.set_mem(LCDC, 0x11);
+    for row in 0..8 {
+        console.set_mem(0x8010 + row * 2, 0xFF);
+        console.set_mem(0x8011 + row * 2, 0xFF);
+        console.set_mem(0x8020 + row * 2, 0xF0);
+        console.set_mem(0x8030 + row * 2, 0xFF);
+    }
+    console.set_mem(0x8040, 0xFF);
+    console.set_mem(0x8041, 0xFF);
+    console.set_mem(BGP, 0b11_10_01_00);
+    console.set_mem(OBP0, 0b11_10_01_00);
+    console.set_mem(OBP1, 0b00_00_10_00);
+}
+
+fn run_objects(console: &mut Console, lcdc: u8) {
+    console.set_mem(LCDC, lcdc);
+    console.run_frame();
+    console.run_frame();
+}
+
+#[test]
+fn objects_use_their_palette_and_flips() {
+    let mut console = idle_console();
+    load_object_tiles(&mut console);
+    put_object(&mut console, 0, 16, 8, 2, 0x00);
+    put_object(&mut console, 1, 16, 24, 2, 0x30);
+    put_object(&mut console, 2, 32, 8, 4, 0x40);
+    run_objects(&mut console, 0x93);
+
+    assert_eq!(shade(&console, 0, 0), 1);
+    assert_eq!(shade(&console, 3, 7), 1);
+    assert_eq!(shade(&console, 4, 0), 0);
+    // X flipped, with OBP1
+    assert_eq!(shade(&console, 16, 0), 0);
+    assert_eq!(shade(&console, 20, 0), 2);
+    // Y flipped
+    assert_eq!(shade(&console, 0, 16), 0);
+    assert_eq!(shade(&console, 0, 23), 3);
+}
+
+#[test]
+fn objects_are_hidden_without_lcdc_bit_1() {
+    let mut console = idle_console();
+    load_object_tiles(&mut console);
+    put_object(&mut console, 0, 16, 8, 1, 0x00);
+    run_objects(&mut console, 0x91);
+
+    assert_eq!(shade(&console, 0, 0), 0);
+}
+
+#[test]
+fn leftmost_object_then_lowest_oam_index_wins() {
+    let mut console = idle_console();
+    load_object_tiles(&mut console);
+    put_object(&mut console, 1, 16, 12, 1, 0x00);
+    put_object(&mut console, 2, 16, 10, 2, 0x00);
+    put_object(&mut console, 0, 16, 40, 2, 0x00);
+    put_object(&mut console, 5, 16, 40, 1, 0x00);
+    run_objects(&mut console, 0x93);
+
+    // The object at X = 10 is in front, except where transparent
+    assert_eq!(shade(&console, 2, 0), 1);
+    assert_eq!(shade(&console, 5, 0), 1);
+    assert_eq!(shade(&console, 6, 0), 3);
+    assert_eq!(shade(&console, 11, 0), 3);
+    // Same X, OAM entry 0 is in front
+    assert_eq!(shade(&console, 32, 0), 1);
+    assert_eq!(shade(&console, 36, 0), 3);
+}
+
+#[test]
+fn bg_priority_hides_objects_behind_bg_colors_1_to_3() {
+    let mut console = idle_console();
+    load_object_tiles(&mut console);
+    // Color 3 of the background shows as white, the object stays behind it
+    console.set_mem(BGP, 0b00_10_01_00);
+    console.set_mem(0x9800 + 10, 1);
+    put_object(&mut console, 0, 16, 88, 2, 0x80);
+    put_object(&mut console, 1, 16, 104, 2, 0x80);
+    run_objects(&mut console, 0x93);
+
+    assert_eq!(shade(&console, 80, 0), 0);
+    assert_eq!(shade(&console, 96, 0), 1);
+}
+
+#[test]
+fn ten_objects_per_line_and_tall_objects() {
+    let mut console = idle_console();
+    load_object_tiles(&mut console);
+    // Line 0 is the bottom half of these, tile 1
+    for i in 0..11 {
+        put_object(&mut console, i, 8, 8 + 8 * i as u8, 1, 0x00);
+    }
+    // 8x16 ignores bit 0 of the tile, tile 2 on top of tile 3
+    put_object(&mut console, 11, 48, 8, 3, 0x00);
+    run_objects(&mut console, 0x97);
+
+    assert_eq!(shade(&console, 72, 0), 3);
+    assert_eq!(shade(&console, 80, 0), 0);
+    assert_eq!(shade(&console, 4, 32), 0);
+    assert_eq!(shade(&console, 4, 40), 1);
+    assert_eq!(shade(&console, 4, 47), 1);
+}
+
+// Mode 3 length on line 1 with the LCD restarted with objects on, checked
+// 4 dots either side of where it should end.
+fn assert_mode_3_ends(console: &mut Console, dot: u64) {
+    restart_lcd(console);
+    console.set_mem(LCDC, 0x93);
+    advance_dots(console, 452 + (dot - 4) / 4 * 4);
+    assert_eq!(mode(console), 3, "{dot}");
+    advance_dots(console, 8);
+    assert_eq!(mode(console), 0, "{dot}");
+}
+
+#[test]
+fn objects_lengthen_mode_3() {
+    let mut console = idle_console();
+    load_object_tiles(&mut console);
+    // Waiting for the first tile and fetching the object: 11 dots
+    put_object(&mut console, 0, 17, 8, 1, 0x00);
+    assert_mode_3_ends(&mut console, 80 + 172 + 11);
+    // A second object in the same tile only adds its own fetch
+    put_object(&mut console, 1, 17, 8, 1, 0x00);
+    assert_mode_3_ends(&mut console, 80 + 172 + 17);
+    // Hidden at X = 0, still 11 dots
+    put_object(&mut console, 1, 17, 0, 1, 0x00);
+    assert_mode_3_ends(&mut console, 80 + 172 + 22);
+}
+
+// A row of solid objects on lines 1 to 8, one every other tile.
+fn put_object_row(console: &mut Console) {
+    load_object_tiles(console);
+    for i in 0..10 {
+        put_object(console, i, 17, 8 + 16 * i as u8, 1, 0x00);
+    }
+    restart_lcd(console);
+    console.set_mem(LCDC, 0x93);
+    advance_dots(console, 456 + 80 + 80);
+    assert_eq!(mode(console), 3);
+}
+
+#[test]
+fn object_palette_and_enable_writes_during_mode_3() {
+    let mut console = idle_console();
+    put_object_row(&mut console);
+    console.set_mem(OBP0, 0b01_00_00_00);
+    advance_dots(&mut console, 300);
+    assert_eq!(shade(&console, 0, 1), 3);
+    assert_eq!(shade(&console, 144, 1), 1);
+
+    // Clearing LCDC bit 1 hides the objects right of the write
+    let mut console = idle_console();
+    put_object_row(&mut console);
+    console.set_mem(LCDC, 0x91);
+    advance_dots(&mut console, 300);
+    assert_eq!(shade(&console, 0, 1), 3);
+    assert_eq!(shade(&console, 144, 1), 0);
+}
+
+// First pixel of line 1 drawn after a BGP write at a fixed point of its
+// mode 3, turning the blank background black.
+fn bgp_switch_pixel(console: &mut Console) -> usize {
+    console.set_mem(BGP, 0b11_10_01_00);
+    restart_lcd(console);
+    console.set_mem(LCDC, 0x93);
+    advance_dots(console, 456 + 80 + 80);
+    console.set_mem(BGP, 0b11_10_01_11);
+    advance_dots(console, 300);
+    (0..SCREEN_WIDTH).position(|x| shade(console, x, 1) == 3).unwrap()
+}
+
+#[test]
+fn object_stalls_delay_the_pixels_after_them() {
+    let mut console = idle_console();
+    load_object_tiles(&mut console);
+    let pixel: usize = bgp_switch_pixel(&mut console);
+    // Hidden at X = 0, each stalls the line by 11 dots
+    put_object(&mut console, 0, 17, 0, 1, 0x00);
+    assert_eq!(bgp_switch_pixel(&mut console), pixel - 11);
+    put_object(&mut console, 1, 17, 0, 1, 0x00);
+    assert_eq!(bgp_switch_pixel(&mut console), pixel - 22);
+    // Transparent at X = 8, the tile was already waited for
+    put_object(&mut console, 1, 17, 8, 0, 0x00);
+    assert_eq!(bgp_switch_pixel(&mut console), pixel - 11 - 6);
+}
+
+#[test]
+fn cpu_is_locked_out_of_vram_and_oam_by_the_mode() {
+    let mut console = idle_console();
+    restart_lcd(&mut console);
+    // The first line has no OAM scan
+    console.set_mem(0x8000, 0x12);
+    console.set_mem(OAM_BASE, 0x34);
+
+    // Mode 2 of line 1: OAM only
+    advance_dots(&mut console, 456 + 36 - 8);
+    assert_eq!(mode(&console), 2);
+    assert_eq!(console.get_mem(OAM_BASE), 0xFF);
+    assert_eq!(console.get_mem(0x8000), 0x12);
+    console.set_mem(OAM_BASE, 0x56);
+
+    // Mode 3: both
+    advance_dots(&mut console, 76);
+    assert_eq!(mode(&console), 3);
+    assert_eq!(console.get_mem(0x8000), 0xFF);
+    assert_eq!(console.get_mem(OAM_BASE), 0xFF);
+    console.set_mem(0x8000, 0x78);
+
+    // Mode 0: neither, and the writes were dropped
+    advance_dots(&mut console, 168);
+    assert_eq!(mode(&console), 0);
+    assert_eq!(console.get_mem(0x8000), 0x12);
+    assert_eq!(console.get_mem(OAM_BASE), 0x34);
+}
+
+// CGB title with the LCD off.
+fn cgb_console() -> Console<'static> {
+    let mut console = idle_cgb_console();
+    console.set_mem(LCDC, 0x11);
+    console
+}
+
+// Auto-incrementing writes of 15-bit colors from the start of palette RAM.
+fn write_palettes(console: &mut Console, spec: usize, colors: &[u16]) {
+    console.set_mem(spec, 0x80);
+    for color in colors {
+        for byte in color.to_le_bytes() {
+            console.set_mem(spec + 1, byte);
+        }
+    }
+}
+
+fn color(console: &Console, x: usize, y: usize) -> u16 {
+    console.colors()[y * SCREEN_WIDTH + x]
+}
+
+const RED: u16 = 0x001F;
+const GREEN: u16 = 0x03E0;
+const BLUE: u16 = 0x7C00;
+const WHITE: u16 = 0x7FFF;
+
+#[test]
+fn cgb_vram_and_wram_banks() {
+    let mut console = cgb_console();
+    console.set_mem(0x8000, 0x12);
+    console.set_mem(VBK, 0x01);
+    assert_eq!(console.get_mem(VBK), 0xFF);
+    assert_eq!(console.get_mem(0x8000), 0x00);
+    console.set_mem(0x8000, 0x34);
+    console.set_mem(VBK, 0x00);
+    assert_eq!(console.get_mem(0x8000), 0x12);
+
+    // Bank 0 selects bank 1
+    console.set_mem(SVBK, 0x00);
+    console.set_mem(0xD000, 0x56);
+    console.set_mem(SVBK, 0x02);
+    assert_eq!(console.get_mem(SVBK), 0xFA);
+    assert_eq!(console.get_mem(0xD000), 0x00);
+    assert_eq!(console.get_mem(0xC000), console.get_mem(0xE000));
+    console.set_mem(0xF000, 0x78);
+    assert_eq!(console.get_mem(0xD000), 0x78);
+    console.set_mem(SVBK, 0x01);
+    assert_eq!(console.get_mem(0xD000), 0x56);
+}
+
+#[test]
+fn cgb_registers_are_absent_for_dmg_titles() {
+    let mut console = idle_console();
+    console.set_mem(LCDC, 0x11);
+    console.set_mem(0x8000, 0x12);
+    console.set_mem(VBK, 0x01);
+    assert_eq!(console.get_mem(0x8000), 0x12);
+    assert_eq!(console.get_mem(SVBK), 0xFF);
+}
+
+#[test]
+fn palette_ram_auto_increments() {
+    let mut console = cgb_console();
+    write_palettes(&mut console, BCPS, &[RED, GREEN]);
+    assert_eq!(console.get_mem(BCPS), 0xC4);
+
+    // Reads do not increment
+    console.set_mem(BCPS, 0x81);
+    assert_eq!(console.get_mem(BCPD), 0x00);
+    assert_eq!(console.get_mem(BCPD), 0x00);
+    console.set_mem(BCPS, 0x02);
+    assert_eq!(console.get_mem(BCPD), 0xE0);
+    console.set_mem(BCPD, 0xAA);
+    assert_eq!(console.get_mem(BCPS), 0x42);
+    assert_eq!(console.get_mem(BCPD), 0xAA);
+
+    // Object palettes are separate, the index wraps around
+    console.set_mem(OCPS, 0xBF);
+    console.set_mem(OCPD, 0x11);
+    assert_eq!(console.get_mem(OCPS), 0xC0);
+    console.set_mem(OCPS, 0x3F);
+    assert_eq!(console.get_mem(OCPD), 0x11);
+}
+
+#[test]
+fn cgb_background_attributes() {
+    let mut console = cgb_console();
+    // Palette 0: white, green, -, -. Palette 1: -, -, -, red.
+    write_palettes(&mut console, BCPS, &[WHITE, GREEN, 0, 0, 0, 0, 0, RED]);
+    // Tile 1 solid color 3 in bank 1 only, tile 2 color 1 on the left in
+    // bank 0, tile 3 color 1 on the top row in bank 0
+    console.set_mem(VBK, 0x01);
+    for row in 0..8 {
+        console.set_mem(0x8010 + row * 2, 0xFF);
+        console.set_mem(0x8011 + row * 2, 0xFF);
+    }
+    // Attributes: palette 1 and bank 1, then X flip, then Y flip
+    console.set_mem(0x9800, 0x09);
+    console.set_mem(0x9801, 0x20);
+    console.set_mem(0x9802, 0x40);
+    console.set_mem(VBK, 0x00);
+    for row in 0..8 {
+        console.set_mem(0x8020 + row * 2, 0xF0);
+    }
+    console.set_mem(0x8030, 0xFF);
+    console.set_mem(0x9800, 0x01);
+    console.set_mem(0x9801, 0x02);
+    console.set_mem(0x9802, 0x03);
+    console.set_mem(LCDC, 0x91);
+    console.run_frame();
+    console.run_frame();
+
+    assert_eq!(color(&console, 0, 0), RED);
+    assert_eq!(&console.rgba()[..4], &[0xFF, 0x00, 0x00, 0xFF]);
+    assert_eq!(color(&console, 8, 0), WHITE);
+    assert_eq!(color(&console, 12, 0), GREEN);
+    assert_eq!(color(&console, 16, 0), WHITE);
+    assert_eq!(color(&console, 16, 7), GREEN);
+    // Tile 0 of map row 1, palette 0 color 0
+    assert_eq!(color(&console, 0, 8), WHITE);
+}
+
+#[test]
+fn cgb_objects_priority() {
+    let mut console = cgb_console();
+    write_palettes(&mut console, BCPS, &[WHITE, 0, 0, BLUE]);
+    // Object palette 0 color 3 red, palette 2 color 3 green
+    write_palettes(&mut console, OCPS, &[0, 0, 0, RED, 0, 0, 0, 0, 0, 0, 0, GREEN]);
+    for row in 0..8 {
+        console.set_mem(0x8010 + row * 2, 0xFF);
+        console.set_mem(0x8011 + row * 2, 0xFF);
+    }
+    // Background tile 1 at map columns 4, 5 and 7, with the priority bit at
+    // column 5
+    console.set_mem(0x9804, 0x01);
+    console.set_mem(0x9805, 0x01);
+    console.set_mem(0x9807, 0x01);
+    console.set_mem(VBK, 0x01);
+    console.set_mem(0x9805, 0x80);
+    console.set_mem(VBK, 0x00);
+
+    // The first object in OAM wins even though it is further right
+    put_object(&mut console, 0, 16, 12, 1, 0x02);
+    put_object(&mut console, 1, 16, 8, 1, 0x00);
+    // Over the background, behind it through either priority bit
+    put_object(&mut console, 2, 16, 40, 1, 0x00);
+    put_object(&mut console, 3, 16, 48, 1, 0x00);
+    put_object(&mut console, 4, 16, 64, 1, 0x80);
+    run_objects(&mut console, 0x93);
+
+    assert_eq!(color(&console, 0, 0), RED);
+    assert_eq!(color(&console, 4, 0), GREEN);
+    assert_eq!(color(&console, 11, 0), GREEN);
+    assert_eq!(color(&console, 32, 0), RED);
+    assert_eq!(color(&console, 40, 0), BLUE);
+    assert_eq!(color(&console, 56, 0), BLUE);
+
+    // Without LCDC bit 0 objects are always in front
+    run_objects(&mut console, 0x92);
+    assert_eq!(color(&console, 40, 0), RED);
+    assert_eq!(color(&console, 56, 0), RED);
+}
@@ -0,0 +1,247 @@
+mod common;
+
+use common::console_with_program;
+use console::{Console, Model};
+use constants::{cond, flag, intr, reg8, DIV, IE, IF, SB, SC, TAC, TIMA, TMA};
+
+// M-cycles of every unprefixed opcode with conditional branches not taken, 0
+// for the ones that are not measured (STOP, HALT, CB prefix and illegal).
+// https://github.com/Gekkio/mooneye-test-suite/blob/main/acceptance/instr_timing.s
+static UNPREFIXED_TIMINGS: [u64; 256] = [
+    1, 3, 2, 2, 1, 1, 2, 1, 5, 2, 2, 2, 1, 1, 2, 1,
+    0, 3, 2, 2, 1, 1, 2, 1, 3, 2, 2, 2, 1, 1, 2, 1,
+    2, 3, 2, 2, 1, 1, 2, 1, 2, 2, 2, 2, 1, 1, 2, 1,
+    2, 3, 2, 2, 3, 3, 3, 1, 2, 2, 2, 2, 1, 1, 2, 1,
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+    2, 2, 2, 2, 2, 2, 0, 2, 1, 1, 1, 1, 1, 1, 2, 1,
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+    2, 3, 3, 4, 3, 4, 2, 4, 2, 4, 3, 0, 3, 6, 2, 4,
+    2, 3, 3, 0, 3, 4, 2, 4, 2, 4, 3, 0, 3, 0, 2, 4,
+    3, 3, 2, 0, 0, 4, 2, 4, 4, 1, 4, 0, 0, 0, 2, 4,
+    3, 3, 2, 1, 0, 4, 2, 4, 3, 2, 4, 1, 0, 0, 2, 4,
+];
+
+fn measure(console: &mut Console) -> u64 {
+    let start: u64 = console.cycles();
+    console.step();
+    (console.cycles() - start) / 4
+}
+
+#[test]
+fn unprefixed_instruction_timings() {
+    for (opcode, expected) in UNPREFIXED_TIMINGS.iter().enumerate() {
+        if *expected == 0 {
+            continue;
+        }
+        let mut console = console_with_program(&[opcode as u8], Model::Dmg, false);
+        // Conditions on Z/C are not taken with the flags clear, NZ/NC ones
+        // with the flags set
+        let on_set_flag: bool = matches!(opcode, 0x28 | 0x38 | 0xC8 | 0xCA | 0xCC | 0xD8 | 0xDA | 0xDC);
+        if on_set_flag {
+            console.clear_flags(&[flag::Z, flag::C]);
+        } else {
+            console.set_flags(&[flag::Z, flag::C]);
+        }
+        assert_eq!(measure(&mut console), *expected, "opcode 0x{:02X}", opcode);
+    }
+}
+
+#[test]
+fn taken_branches_take_longer() {
+    // JR, RET, JP and CALL on NZ, each taken when Z is clear
+    for (opcode, expected) in [(0x20u8, 3u64), (0xC0, 5), (0xC2, 4), (0xC4, 6)] {
+        let mut console = console_with_program(&[opcode], Model::Dmg, false);
+        console.clear_flag(flag::Z);
+        assert!(console.is_condition_met(cond::NZ));
+        assert_eq!(measure(&mut console), expected, "opcode 0x{:02X}", opcode);
+    }
+}
+
+#[test]
+fn cb_prefixed_instruction_timings() {
+    for opcode in 0..=0xFFu8 {
+        let expected: u64 = match (opcode >> 6, opcode & 0x07) {
+            (_, 6) if opcode >> 6 == 1 => 3,
+            (_, 6) => 4,
+            _ => 2,
+        };
+        let mut console = console_with_program(&[0xCB, opcode], Model::Dmg, false);
+        assert_eq!(measure(&mut console), expected, "opcode CB 0x{:02X}", opcode);
+    }
+}
+
+#[test]
+fn reads_happen_on_their_own_mcycle() {
+    // TIMA ticks every 4 M-cycles. In LDH A,[TIMA]; NOP; NOP; LDH A,[TIMA]
+    // the two reads are 4 M-cycles apart, so whatever the timer phase they
+    // must see consecutive values.
+    let mut console = console_with_program(&[0xF0, TIMA as u8, 0x00, 0x00, 0xF0, TIMA as u8], Model::Dmg, false);
+    console.set_mem(TAC, 0b101);
+    console.set_mem(TIMA, 0x00);
+
+    console.step();
+    let first: u8 = console.get_r8(reg8::A);
+    console.step();
+    console.step();
+    console.step();
+    let second: u8 = console.get_r8(reg8::A);
+    assert_eq!(second, first.wrapping_add(1));
+}
+
+// Timer with `tac` just enabled on a system counter of 8, TIMA at `tima`.
+// Every M-cycle adds 4 to the counter, TAC 0b101 ticks on the falling edge of
+// its bit 3 at 16, so each following write lands on a known counter value.
+fn aligned_timer(tac: u8, tima: u8) -> Console<'static> {
+    let mut console = console_with_program(&[0x00], Model::Dmg, false);
+    console.set_mem(TAC, 0b000);
+    console.set_mem(TMA, 0xAB);
+    console.set_mem(DIV, 0x00);
+    console.set_mem(TIMA, tima);
+    console.set_mem(TAC, tac);
+    console
+}
+
+#[test]
+fn div_write_with_the_selected_bit_high_increments_tima() {
+    let mut console = aligned_timer(0b101, 0x00);
+    // Counter 12
+    console.set_mem(DIV, 0x00);
+    assert_eq!(console.peek_mem(TIMA), 1);
+
+    // Resetting every M-cycle keeps the counter below 8, TIMA never ticks
+    for _ in 0..100 {
+        console.set_mem(DIV, 0x00);
+    }
+    assert_eq!(console.peek_mem(TIMA), 1);
+}
+
+#[test]
+fn tac_write_glitch() {
+    // Disabling with the selected bit high
+    let mut console = aligned_timer(0b101, 0x00);
+    console.set_mem(TAC, 0b001);
+    assert_eq!(console.peek_mem(TIMA), 1);
+
+    // Switching to bit 9, low, from bit 3, high
+    let mut console = aligned_timer(0b101, 0x00);
+    console.set_mem(TAC, 0b100);
+    assert_eq!(console.peek_mem(TIMA), 1);
+
+    // Enabling never ticks, nor does disabling with the bit low
+    let mut console = aligned_timer(0b101, 0x00);
+    console.mcycle();
+    console.mcycle();
+    assert_eq!(console.peek_mem(TIMA), 1);
+    console.set_mem(TAC, 0b001);
+    console.set_mem(TAC, 0b101);
+    assert_eq!(console.peek_mem(TIMA), 1);
+}
+
+#[test]
+fn tima_write_in_the_overflow_mcycle_cancels_the_reload() {
+    let mut console = aligned_timer(0b101, 0xFF);
+    console.set_mem(IF, 0x00);
+    // Overflows at counter 16, the write lands in the same M-cycle
+    console.set_mem(TIMA, 0x12);
+    console.mcycle();
+    console.mcycle();
+    assert_eq!(console.peek_mem(TIMA), 0x12);
+    assert_eq!(console.peek_mem(IF) & intr::TIMER, 0);
+}
+
+#[test]
+fn tima_write_in_the_reload_mcycle_is_ignored() {
+    let mut console = aligned_timer(0b101, 0xFF);
+    console.set_mem(IF, 0x00);
+    console.mcycle();
+    // Overflowed, TIMA reads 0 until TMA is loaded one M-cycle later
+    assert_eq!(console.peek_mem(TIMA), 0x00);
+    assert_eq!(console.peek_mem(IF) & intr::TIMER, 0);
+    console.set_mem(TIMA, 0x12);
+    assert_eq!(console.peek_mem(TIMA), 0xAB);
+    assert_ne!(console.peek_mem(IF) & intr::TIMER, 0);
+
+    // Back to normal on the next one
+    console.set_mem(TIMA, 0x12);
+    assert_eq!(console.peek_mem(TIMA), 0x12);
+}
+
+#[test]
+fn tma_write_in_the_reload_mcycle_goes_through_to_tima() {
+    let mut console = aligned_timer(0b101, 0xFF);
+    console.set_mem(IF, 0x00);
+    console.mcycle();
+    console.set_mem(TMA, 0x34);
+    assert_eq!(console.peek_mem(TIMA), 0x34);
+    assert_ne!(console.peek_mem(IF) & intr::TIMER, 0);
+
+    // One M-cycle later only TMA changes
+    let mut console = aligned_timer(0b101, 0xFF);
+    console.set_mem(IF, 0x00);
+    console.mcycle();
+    console.mcycle();
+    console.set_mem(TMA, 0x34);
+    assert_eq!(console.peek_mem(TIMA), 0xAB);
+    assert_eq!(console.peek_mem(TMA), 0x34);
+}
+
+#[test]
+fn serial_transfer_without_partner() {
+    let mut console = console_with_program(&[0x00], Model::Dmg, false);
+    console.set_mem(SB, 0x42);
+    console.set_mem(SC, 0x81);
+    assert_eq!(console.peek_mem(SC) & 0x80, 0x80);
+
+    // 8 bits at 8192 Hz take 4096 T-cycles
+    for _ in 0..1024 {
+        console.mcycle();
+    }
+    assert_eq!(console.peek_mem(SC) & 0x80, 0);
+    assert_eq!(console.peek_mem(SB), 0xFF);
+    assert_ne!(console.peek_mem(IF) & intr::SERIAL, 0);
+    assert_eq!(console.take_serial_output(), [0x42]);
+    assert!(console.take_serial_output().is_empty());
+}
+
+#[test]
+fn serial_external_clock_never_completes() {
+    let mut console = console_with_program(&[0x00], Model::Dmg, false);
+    console.set_mem(SB, 0x42);
+    console.set_mem(SC, 0x80);
+
+    for _ in 0..4096 {
+        console.mcycle();
+    }
+    assert_eq!(console.peek_mem(SC) & 0x80, 0x80);
+    assert_eq!(console.peek_mem(SB), 0x42);
+    assert!(console.take_serial_output().is_empty());
+}
+
+#[test]
+fn halt_skips_ahead_to_the_timer_interrupt() {
+    // HALT; NOP with TIMA one increment away from overflowing
+    let mut console = console_with_program(&[0x76, 0x00], Model::Dmg, false);
+    console.set_mem(IE, intr::TIMER);
+    console.set_mem(TIMA, 0xFF);
+    console.set_mem(TAC, 0b100);
+    console.step();
+    assert!(console.is_halted());
+
+    // TIMA overflows within 1024 T-cycles and IF is set one M-cycle later,
+    // without the CPU going through every M-cycle in between.
+    let start: u64 = console.cycles();
+    let mut steps: u32 = 0;
+    while console.peek_mem(IF) & intr::TIMER == 0 {
+        console.step();
+        steps += 1;
+    }
+    assert!(steps <= 3);
+    assert!(console.cycles() - start <= 1024 + 4);
+    console.step();
+    assert!(!console.is_halted());
+}
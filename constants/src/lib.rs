@@ -4,6 +4,7 @@ pub const VRAM_BASE: usize = 0x8000;
 pub const ERAM_BASE: usize = 0xA000;
 pub const WRAM_BASE: usize = 0xC000;
 pub const UNUSED_RAM_BASE: usize = 0xD000;
+pub const ECHO_RAM_BASE: usize = 0xE000;
 pub const OAM_BASE: usize = 0xFE00;
 pub const PROHIBITED_BASE: usize = 0xFEA0;
 pub const IO_REGS_BASE: usize = 0xFF00;
@@ -13,13 +14,34 @@ pub const IE: usize = 0xFFFF;
 pub const PALETTES_BASE: usize = 0xFF47;
 pub const PALETTES_END: usize = 0xFF50;
 
+pub const P1: usize = 0xFF00;
+pub const DIV: usize = 0xFF04;
+pub const TIMA: usize = 0xFF05;
+pub const TMA: usize = 0xFF06;
+pub const TAC: usize = 0xFF07;
 pub const IF: usize = 0xFF0F;
-pub const LY: usize = 0xFF44;
+pub const NR52: usize = 0xFF26;
 pub const LCDC: usize = 0xFF40;
-pub const SCX: usize = 0xFF42;
-pub const SCY: usize = 0xFF43;
-pub const WX: usize =  0xFF4A;
-pub const WY: usize = 0xFF4B;
+pub const STAT: usize = 0xFF41;
+pub const SCY: usize = 0xFF42;
+pub const SCX: usize = 0xFF43;
+pub const LY: usize = 0xFF44;
+pub const LYC: usize = 0xFF45;
+pub const DMA: usize = 0xFF46;
+pub const BGP: usize = 0xFF47;
+pub const OBP0: usize = 0xFF48;
+pub const OBP1: usize = 0xFF49;
+pub const WY: usize = 0xFF4A;
+pub const WX: usize = 0xFF4B;
+pub const KEY1: usize = 0xFF4D;
+pub const VBK: usize = 0xFF4F;
+pub const BCPS: usize = 0xFF68;
+pub const BCPD: usize = 0xFF69;
+pub const OCPS: usize = 0xFF6A;
+pub const OCPD: usize = 0xFF6B;
+pub const SVBK: usize = 0xFF70;
+
+pub const BOOT: usize = 0xFF50;
 
 pub const OAM_SIZE: usize = 0xA0;
 
@@ -49,6 +71,7 @@ pub mod intr {
     pub const TIMER: u8     = 0b00000100;
     pub const SERIAL: u8    = 0b00001000;
     pub const JOYPAD: u8    = 0b00010000;
+    pub const ALL: u8       = 0b00011111;
 
     pub fn intr_to_name(mask: u8) -> String {
         match mask {
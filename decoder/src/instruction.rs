@@ -0,0 +1,231 @@
+use std::fmt;
+
+use constants::{cond, reg16, reg16mem, reg16stk, reg8};
+
+// Operation of the 8 bit arithmetic/logic instructions, in opcode order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AluOp {
+    Add,
+    Adc,
+    Sub,
+    Sbc,
+    And,
+    Xor,
+    Or,
+    Cp,
+}
+
+// Rotates, shifts and SWAP of the CB prefixed block, in opcode order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShiftOp {
+    Rlc,
+    Rrc,
+    Rl,
+    Rr,
+    Sla,
+    Sra,
+    Swap,
+    Srl,
+}
+
+// Register operands are the indices from `constants` (reg8, reg16, reg16mem,
+// reg16stk and cond), exactly as they are encoded in the opcode.
+// https://gbdev.io/pandocs/CPU_Instruction_Set.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    // Block 0
+    Nop,
+    LdR16Imm16(u8, u16),
+    LdR16MemA(u8),
+    LdAR16Mem(u8),
+    LdImm16Sp(u16),
+    IncR16(u8),
+    DecR16(u8),
+    AddHlR16(u8),
+    IncR8(u8),
+    DecR8(u8),
+    LdR8Imm8(u8, u8),
+    Rlca,
+    Rrca,
+    Rla,
+    Rra,
+    Daa,
+    Cpl,
+    Scf,
+    Ccf,
+    // Signed offset, relative to the address of the next instruction
+    JrImm8(u8),
+    JrCondImm8(u8, u8),
+    Stop,
+
+    // Block 1 (destination, source)
+    LdR8R8(u8, u8),
+    Halt,
+
+    // Block 2
+    AluAR8(AluOp, u8),
+
+    // Block 3
+    AluAImm8(AluOp, u8),
+    RetCond(u8),
+    Ret,
+    Reti,
+    JpCondImm16(u8, u16),
+    JpImm16(u16),
+    JpHl,
+    CallCondImm16(u8, u16),
+    CallImm16(u16),
+    Rst(u8),
+    Pop(u8),
+    Push(u8),
+    LdhCA,
+    LdhImm8A(u8),
+    LdImm16A(u16),
+    LdhAC,
+    LdhAImm8(u8),
+    LdAImm16(u16),
+    AddSpImm8(u8),
+    LdHlSpImm8(u8),
+    LdSpHl,
+    Di,
+    Ei,
+    // 0xCB, only ever seen in the unprefixed table
+    Prefix,
+    Illegal(u8),
+
+    // CB prefixed block
+    Shift(ShiftOp, u8),
+    Bit(u8, u8),
+    Res(u8, u8),
+    Set(u8, u8),
+}
+
+impl Instruction {
+    // Number of immediate bytes following the opcode.
+    pub fn operand_len(&self) -> usize {
+        match self {
+            Instruction::LdR16Imm16(..) | Instruction::LdImm16Sp(_) | Instruction::JpCondImm16(..) |
+            Instruction::JpImm16(_) | Instruction::CallCondImm16(..) | Instruction::CallImm16(_) |
+            Instruction::LdImm16A(_) | Instruction::LdAImm16(_) => 2,
+            Instruction::LdR8Imm8(..) | Instruction::JrImm8(_) | Instruction::JrCondImm8(..) |
+            Instruction::AluAImm8(..) | Instruction::LdhImm8A(_) | Instruction::LdhAImm8(_) |
+            Instruction::AddSpImm8(_) | Instruction::LdHlSpImm8(_) => 1,
+            _ => 0,
+        }
+    }
+
+    // Fills in the immediate operand of an instruction taken from a table.
+    pub fn with_operand(self, imm: u16) -> Instruction {
+        let imm8: u8 = imm as u8;
+        match self {
+            Instruction::LdR16Imm16(r16, _) => Instruction::LdR16Imm16(r16, imm),
+            Instruction::LdImm16Sp(_) => Instruction::LdImm16Sp(imm),
+            Instruction::JpCondImm16(cc, _) => Instruction::JpCondImm16(cc, imm),
+            Instruction::JpImm16(_) => Instruction::JpImm16(imm),
+            Instruction::CallCondImm16(cc, _) => Instruction::CallCondImm16(cc, imm),
+            Instruction::CallImm16(_) => Instruction::CallImm16(imm),
+            Instruction::LdImm16A(_) => Instruction::LdImm16A(imm),
+            Instruction::LdAImm16(_) => Instruction::LdAImm16(imm),
+            Instruction::LdR8Imm8(r8, _) => Instruction::LdR8Imm8(r8, imm8),
+            Instruction::JrImm8(_) => Instruction::JrImm8(imm8),
+            Instruction::JrCondImm8(cc, _) => Instruction::JrCondImm8(cc, imm8),
+            Instruction::AluAImm8(op, _) => Instruction::AluAImm8(op, imm8),
+            Instruction::LdhImm8A(_) => Instruction::LdhImm8A(imm8),
+            Instruction::LdhAImm8(_) => Instruction::LdhAImm8(imm8),
+            Instruction::AddSpImm8(_) => Instruction::AddSpImm8(imm8),
+            Instruction::LdHlSpImm8(_) => Instruction::LdHlSpImm8(imm8),
+            _ => self,
+        }
+    }
+}
+
+impl AluOp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AluOp::Add => "ADD",
+            AluOp::Adc => "ADC",
+            AluOp::Sub => "SUB",
+            AluOp::Sbc => "SBC",
+            AluOp::And => "AND",
+            AluOp::Xor => "XOR",
+            AluOp::Or => "OR",
+            AluOp::Cp => "CP",
+        }
+    }
+}
+
+impl ShiftOp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShiftOp::Rlc => "RLC",
+            ShiftOp::Rrc => "RRC",
+            ShiftOp::Rl => "RL",
+            ShiftOp::Rr => "RR",
+            ShiftOp::Sla => "SLA",
+            ShiftOp::Sra => "SRA",
+            ShiftOp::Swap => "SWAP",
+            ShiftOp::Srl => "SRL",
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Instruction::Nop => write!(f, "NOP"),
+            Instruction::LdR16Imm16(r16, imm16) => write!(f, "LD {}, 0x{:04X}", reg16::reg_to_name(r16), imm16),
+            Instruction::LdR16MemA(r16) => write!(f, "LD [{}], A", reg16mem::reg_to_name(r16)),
+            Instruction::LdAR16Mem(r16) => write!(f, "LD A, [{}]", reg16mem::reg_to_name(r16)),
+            Instruction::LdImm16Sp(imm16) => write!(f, "LD [0x{:04X}], SP", imm16),
+            Instruction::IncR16(r16) => write!(f, "INC {}", reg16::reg_to_name(r16)),
+            Instruction::DecR16(r16) => write!(f, "DEC {}", reg16::reg_to_name(r16)),
+            Instruction::AddHlR16(r16) => write!(f, "ADD HL, {}", reg16::reg_to_name(r16)),
+            Instruction::IncR8(r8) => write!(f, "INC {}", reg8::reg_to_name(r8)),
+            Instruction::DecR8(r8) => write!(f, "DEC {}", reg8::reg_to_name(r8)),
+            Instruction::LdR8Imm8(r8, imm8) => write!(f, "LD {}, 0x{:02X}", reg8::reg_to_name(r8), imm8),
+            Instruction::Rlca => write!(f, "RLCA"),
+            Instruction::Rrca => write!(f, "RRCA"),
+            Instruction::Rla => write!(f, "RLA"),
+            Instruction::Rra => write!(f, "RRA"),
+            Instruction::Daa => write!(f, "DAA"),
+            Instruction::Cpl => write!(f, "CPL"),
+            Instruction::Scf => write!(f, "SCF"),
+            Instruction::Ccf => write!(f, "CCF"),
+            Instruction::JrImm8(imm8) => write!(f, "JR {:+}", imm8 as i8),
+            Instruction::JrCondImm8(cc, imm8) => write!(f, "JR {}, {:+}", cond::get_cond_name(cc), imm8 as i8),
+            Instruction::Stop => write!(f, "STOP"),
+            Instruction::LdR8R8(dst, src) => write!(f, "LD {}, {}", reg8::reg_to_name(dst), reg8::reg_to_name(src)),
+            Instruction::Halt => write!(f, "HALT"),
+            Instruction::AluAR8(op, r8) => write!(f, "{} A, {}", op.name(), reg8::reg_to_name(r8)),
+            Instruction::AluAImm8(op, imm8) => write!(f, "{} A, 0x{:02X}", op.name(), imm8),
+            Instruction::RetCond(cc) => write!(f, "RET {}", cond::get_cond_name(cc)),
+            Instruction::Ret => write!(f, "RET"),
+            Instruction::Reti => write!(f, "RETI"),
+            Instruction::JpCondImm16(cc, imm16) => write!(f, "JP {}, 0x{:04X}", cond::get_cond_name(cc), imm16),
+            Instruction::JpImm16(imm16) => write!(f, "JP 0x{:04X}", imm16),
+            Instruction::JpHl => write!(f, "JP HL"),
+            Instruction::CallCondImm16(cc, imm16) => write!(f, "CALL {}, 0x{:04X}", cond::get_cond_name(cc), imm16),
+            Instruction::CallImm16(imm16) => write!(f, "CALL 0x{:04X}", imm16),
+            Instruction::Rst(tgt3) => write!(f, "RST 0x{:02X}", tgt3 << 3),
+            Instruction::Pop(r16stk) => write!(f, "POP {}", reg16stk::reg_to_name(r16stk)),
+            Instruction::Push(r16stk) => write!(f, "PUSH {}", reg16stk::reg_to_name(r16stk)),
+            Instruction::LdhCA => write!(f, "LDH [C], A"),
+            Instruction::LdhImm8A(imm8) => write!(f, "LDH [0xFF{:02X}], A", imm8),
+            Instruction::LdImm16A(imm16) => write!(f, "LD [0x{:04X}], A", imm16),
+            Instruction::LdhAC => write!(f, "LDH A, [C]"),
+            Instruction::LdhAImm8(imm8) => write!(f, "LDH A, [0xFF{:02X}]", imm8),
+            Instruction::LdAImm16(imm16) => write!(f, "LD A, [0x{:04X}]", imm16),
+            Instruction::AddSpImm8(imm8) => write!(f, "ADD SP, {}", imm8 as i8),
+            Instruction::LdHlSpImm8(imm8) => write!(f, "LD HL, SP{:+}", imm8 as i8),
+            Instruction::LdSpHl => write!(f, "LD SP, HL"),
+            Instruction::Di => write!(f, "DI"),
+            Instruction::Ei => write!(f, "EI"),
+            Instruction::Prefix => write!(f, "PREFIX CB"),
+            Instruction::Illegal(opcode) => write!(f, "ILLEGAL 0x{:02X}", opcode),
+            Instruction::Shift(op, r8) => write!(f, "{} {}", op.name(), reg8::reg_to_name(r8)),
+            Instruction::Bit(b3, r8) => write!(f, "BIT {}, {}", b3, reg8::reg_to_name(r8)),
+            Instruction::Res(b3, r8) => write!(f, "RES {}, {}", b3, reg8::reg_to_name(r8)),
+            Instruction::Set(b3, r8) => write!(f, "SET {}, {}", b3, reg8::reg_to_name(r8)),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+mod instruction;
+mod table;
+
+pub use instruction::{AluOp, Instruction, ShiftOp};
+pub use table::{CB_PREFIXED, UNPREFIXED};
+
+pub const CB_PREFIX: u8 = 0xCB;
+
+// Decodes the instruction at the start of `bytes`, returning it along with
+// its length. None if `bytes` ends in the middle of the instruction.
+pub fn decode(bytes: &[u8]) -> Option<(Instruction, usize)> {
+    let opcode: u8 = *bytes.first()?;
+    let (template, opcode_len): (Instruction, usize) = if opcode == CB_PREFIX {
+        (CB_PREFIXED[*bytes.get(1)? as usize], 2)
+    } else {
+        (UNPREFIXED[opcode as usize], 1)
+    };
+
+    let len: usize = opcode_len + template.operand_len();
+    let operand: u16 = match template.operand_len() {
+        1 => *bytes.get(opcode_len)? as u16,
+        2 => u16::from_le_bytes([*bytes.get(opcode_len)?, *bytes.get(opcode_len + 1)?]),
+        _ => 0,
+    };
+    Some((template.with_operand(operand), len))
+}
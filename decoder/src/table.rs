@@ -0,0 +1,124 @@
+use crate::instruction::{AluOp, Instruction, ShiftOp};
+
+// Both tables are built at compile time; immediates are left as 0 and filled
+// in with `Instruction::with_operand` once fetched.
+pub static UNPREFIXED: [Instruction; 256] = build(false);
+pub static CB_PREFIXED: [Instruction; 256] = build(true);
+
+const fn build(cb: bool) -> [Instruction; 256] {
+    let mut table: [Instruction; 256] = [Instruction::Nop; 256];
+    let mut i: usize = 0;
+    while i < 256 {
+        table[i] = if cb { decode_cb(i as u8) } else { decode_unprefixed(i as u8) };
+        i += 1;
+    }
+    table
+}
+
+const fn alu_op(idx: u8) -> AluOp {
+    match idx {
+        0 => AluOp::Add,
+        1 => AluOp::Adc,
+        2 => AluOp::Sub,
+        3 => AluOp::Sbc,
+        4 => AluOp::And,
+        5 => AluOp::Xor,
+        6 => AluOp::Or,
+        _ => AluOp::Cp,
+    }
+}
+
+const fn shift_op(idx: u8) -> ShiftOp {
+    match idx {
+        0 => ShiftOp::Rlc,
+        1 => ShiftOp::Rrc,
+        2 => ShiftOp::Rl,
+        3 => ShiftOp::Rr,
+        4 => ShiftOp::Sla,
+        5 => ShiftOp::Sra,
+        6 => ShiftOp::Swap,
+        _ => ShiftOp::Srl,
+    }
+}
+
+// https://gbdev.io/pandocs/CPU_Instruction_Set.html
+const fn decode_unprefixed(op: u8) -> Instruction {
+    let r8: u8 = (op >> 3) & 0x07;
+    let r16: u8 = (op >> 4) & 0x03;
+    let cc: u8 = (op >> 3) & 0x03;
+
+    match op >> 6 {
+        0 => match op {
+            0x00 => Instruction::Nop,
+            0x08 => Instruction::LdImm16Sp(0),
+            0x10 => Instruction::Stop,
+            0x18 => Instruction::JrImm8(0),
+            0x20 | 0x28 | 0x30 | 0x38 => Instruction::JrCondImm8(cc, 0),
+            0x07 => Instruction::Rlca,
+            0x0F => Instruction::Rrca,
+            0x17 => Instruction::Rla,
+            0x1F => Instruction::Rra,
+            0x27 => Instruction::Daa,
+            0x2F => Instruction::Cpl,
+            0x37 => Instruction::Scf,
+            0x3F => Instruction::Ccf,
+            _ => match op & 0x0F {
+                0x01 => Instruction::LdR16Imm16(r16, 0),
+                0x02 => Instruction::LdR16MemA(r16),
+                0x03 => Instruction::IncR16(r16),
+                0x09 => Instruction::AddHlR16(r16),
+                0x0A => Instruction::LdAR16Mem(r16),
+                0x0B => Instruction::DecR16(r16),
+                _ => match op & 0x07 {
+                    0x04 => Instruction::IncR8(r8),
+                    0x05 => Instruction::DecR8(r8),
+                    _ => Instruction::LdR8Imm8(r8, 0),
+                },
+            },
+        },
+        1 => match op {
+            0x76 => Instruction::Halt,
+            _ => Instruction::LdR8R8(r8, op & 0x07),
+        },
+        2 => Instruction::AluAR8(alu_op(r8), op & 0x07),
+        _ => match op {
+            0xC9 => Instruction::Ret,
+            0xD9 => Instruction::Reti,
+            0xC3 => Instruction::JpImm16(0),
+            0xE9 => Instruction::JpHl,
+            0xCD => Instruction::CallImm16(0),
+            0xCB => Instruction::Prefix,
+            0xE2 => Instruction::LdhCA,
+            0xE0 => Instruction::LdhImm8A(0),
+            0xEA => Instruction::LdImm16A(0),
+            0xF2 => Instruction::LdhAC,
+            0xF0 => Instruction::LdhAImm8(0),
+            0xFA => Instruction::LdAImm16(0),
+            0xE8 => Instruction::AddSpImm8(0),
+            0xF8 => Instruction::LdHlSpImm8(0),
+            0xF9 => Instruction::LdSpHl,
+            0xF3 => Instruction::Di,
+            0xFB => Instruction::Ei,
+            0xC0 | 0xC8 | 0xD0 | 0xD8 => Instruction::RetCond(cc),
+            0xC2 | 0xCA | 0xD2 | 0xDA => Instruction::JpCondImm16(cc, 0),
+            0xC4 | 0xCC | 0xD4 | 0xDC => Instruction::CallCondImm16(cc, 0),
+            _ if op & 0x07 == 0x06 => Instruction::AluAImm8(alu_op(r8), 0),
+            _ if op & 0x07 == 0x07 => Instruction::Rst(r8),
+            _ if op & 0x0F == 0x01 => Instruction::Pop(r16),
+            _ if op & 0x0F == 0x05 => Instruction::Push(r16),
+            _ => Instruction::Illegal(op),
+        },
+    }
+}
+
+const fn decode_cb(op: u8) -> Instruction {
+    let r8: u8 = op & 0x07;
+    let b3: u8 = (op >> 3) & 0x07;
+
+    match op >> 6 {
+        0 => Instruction::Shift(shift_op(b3), r8),
+        1 => Instruction::Bit(b3, r8),
+        2 => Instruction::Res(b3, r8),
+        _ => Instruction::Set(b3, r8),
+    }
+}
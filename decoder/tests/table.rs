@@ -0,0 +1,87 @@
+use decoder::{decode, Instruction, CB_PREFIX, CB_PREFIXED, UNPREFIXED};
+
+// Length in bytes of every unprefixed instruction, opcode included. STOP is
+// 1 here, whether the byte after it is skipped is up to the CPU; the CB
+// prefix is 1 as well, the prefixed table has the rest.
+// https://gbdev.io/pandocs/CPU_Instruction_Set.html
+static UNPREFIXED_LENGTHS: [usize; 256] = [
+    1, 3, 1, 1, 1, 1, 2, 1, 3, 1, 1, 1, 1, 1, 2, 1,
+    1, 3, 1, 1, 1, 1, 2, 1, 2, 1, 1, 1, 1, 1, 2, 1,
+    2, 3, 1, 1, 1, 1, 2, 1, 2, 1, 1, 1, 1, 1, 2, 1,
+    2, 3, 1, 1, 1, 1, 2, 1, 2, 1, 1, 1, 1, 1, 2, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 3, 3, 3, 1, 2, 1, 1, 1, 3, 1, 3, 3, 2, 1,
+    1, 1, 3, 1, 3, 1, 2, 1, 1, 1, 3, 1, 3, 1, 2, 1,
+    2, 1, 1, 1, 1, 1, 2, 1, 2, 1, 3, 1, 1, 1, 2, 1,
+    2, 1, 1, 1, 1, 1, 2, 1, 2, 1, 3, 1, 1, 1, 2, 1,
+];
+
+static ILLEGAL: [u8; 11] = [0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD];
+
+fn disassemble(bytes: &[u8]) -> String {
+    decode(bytes).unwrap().0.to_string()
+}
+
+#[test]
+fn unprefixed_lengths() {
+    for opcode in 0..=0xFFu8 {
+        let expected: usize = UNPREFIXED_LENGTHS[opcode as usize];
+        assert_eq!(1 + UNPREFIXED[opcode as usize].operand_len(), expected, "opcode 0x{:02X}", opcode);
+        if opcode != CB_PREFIX {
+            let bytes: [u8; 3] = [opcode, 0x34, 0x12];
+            assert_eq!(decode(&bytes).unwrap().1, expected, "opcode 0x{:02X}", opcode);
+            assert_eq!(decode(&bytes[..expected - 1]), None, "opcode 0x{:02X}", opcode);
+        }
+    }
+}
+
+#[test]
+fn cb_prefixed_are_all_two_bytes() {
+    for opcode in 0..=0xFFu8 {
+        let instr: Instruction = CB_PREFIXED[opcode as usize];
+        assert_eq!(instr.operand_len(), 0, "opcode CB 0x{:02X}", opcode);
+        assert!(!matches!(instr, Instruction::Illegal(_) | Instruction::Prefix), "opcode CB 0x{:02X}", opcode);
+        assert_eq!(decode(&[CB_PREFIX, opcode]), Some((instr, 2)));
+    }
+    assert_eq!(decode(&[CB_PREFIX]), None);
+}
+
+#[test]
+fn illegal_opcodes() {
+    let illegal: Vec<u8> = (0..=0xFFu8)
+        .filter(|opcode| matches!(UNPREFIXED[*opcode as usize], Instruction::Illegal(_)))
+        .collect();
+    assert_eq!(illegal, ILLEGAL);
+    for opcode in ILLEGAL {
+        assert_eq!(UNPREFIXED[opcode as usize], Instruction::Illegal(opcode));
+    }
+    assert_eq!(UNPREFIXED[CB_PREFIX as usize], Instruction::Prefix);
+}
+
+#[test]
+fn display() {
+    assert_eq!(disassemble(&[0x00]), "NOP");
+    assert_eq!(disassemble(&[0x01, 0x34, 0x12]), "LD BC, 0x1234");
+    assert_eq!(disassemble(&[0x08, 0x00, 0xC0]), "LD [0xC000], SP");
+    assert_eq!(disassemble(&[0x18, 0xFE]), "JR -2");
+    assert_eq!(disassemble(&[0x20, 0x05]), "JR NZ, +5");
+    assert_eq!(disassemble(&[0x22]), "LD [HLI], A");
+    assert_eq!(disassemble(&[0x7E]), "LD A, [HL]");
+    assert_eq!(disassemble(&[0xAF]), "XOR A, A");
+    assert_eq!(disassemble(&[0xE0, 0x40]), "LDH [0xFF40], A");
+    assert_eq!(disassemble(&[0xE8, 0xFE]), "ADD SP, -2");
+    assert_eq!(disassemble(&[0xF8, 0x80]), "LD HL, SP-128");
+    assert_eq!(disassemble(&[0xF1]), "POP AF");
+    assert_eq!(disassemble(&[0xFF]), "RST 0x38");
+    assert_eq!(disassemble(&[0xD3]), "ILLEGAL 0xD3");
+    assert_eq!(disassemble(&[CB_PREFIX, 0x37]), "SWAP A");
+    assert_eq!(disassemble(&[CB_PREFIX, 0x7E]), "BIT 7, [HL]");
+    assert_eq!(disassemble(&[CB_PREFIX, 0xC1]), "SET 0, C");
+}
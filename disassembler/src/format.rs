@@ -0,0 +1,98 @@
+use constants::{cond, reg16, reg16mem, reg16stk, reg8};
+use decoder::Instruction;
+
+fn r8(idx: u8) -> String {
+    reg8::reg_to_name(idx).to_lowercase()
+}
+
+fn r16(idx: u8) -> String {
+    reg16::reg_to_name(idx).to_lowercase()
+}
+
+fn r16stk(idx: u8) -> String {
+    reg16stk::reg_to_name(idx).to_lowercase()
+}
+
+fn r16mem(idx: u8) -> String {
+    match idx {
+        reg16mem::HLI => "hl+".to_string(),
+        reg16mem::HLD => "hl-".to_string(),
+        _ => reg16mem::reg_to_name(idx).to_lowercase(),
+    }
+}
+
+fn cc(idx: u8) -> String {
+    cond::get_cond_name(idx).to_lowercase()
+}
+
+fn signed(imm8: u8) -> i16 {
+    imm8 as i8 as i16
+}
+
+// Formats an instruction in RGBDS syntax. `next` is the address right after
+// the instruction (JR offsets are relative to it) and `target` names an
+// address, either through a label or as a plain number.
+// https://rgbds.gbdev.io/docs/gbz80.7
+pub fn rgbds(instr: &Instruction, next: u16, target: &dyn Fn(u16) -> String) -> String {
+    match *instr {
+        Instruction::Nop => "nop".to_string(),
+        Instruction::LdR16Imm16(idx, imm16) => format!("ld {}, ${:04X}", r16(idx), imm16),
+        Instruction::LdR16MemA(idx) => format!("ld [{}], a", r16mem(idx)),
+        Instruction::LdAR16Mem(idx) => format!("ld a, [{}]", r16mem(idx)),
+        Instruction::LdImm16Sp(imm16) => format!("ld [{}], sp", target(imm16)),
+        Instruction::IncR16(idx) => format!("inc {}", r16(idx)),
+        Instruction::DecR16(idx) => format!("dec {}", r16(idx)),
+        Instruction::AddHlR16(idx) => format!("add hl, {}", r16(idx)),
+        Instruction::IncR8(idx) => format!("inc {}", r8(idx)),
+        Instruction::DecR8(idx) => format!("dec {}", r8(idx)),
+        Instruction::LdR8Imm8(idx, imm8) => format!("ld {}, ${:02X}", r8(idx), imm8),
+        Instruction::Rlca => "rlca".to_string(),
+        Instruction::Rrca => "rrca".to_string(),
+        Instruction::Rla => "rla".to_string(),
+        Instruction::Rra => "rra".to_string(),
+        Instruction::Daa => "daa".to_string(),
+        Instruction::Cpl => "cpl".to_string(),
+        Instruction::Scf => "scf".to_string(),
+        Instruction::Ccf => "ccf".to_string(),
+        Instruction::JrImm8(imm8) => format!("jr {}", target(next.wrapping_add_signed(signed(imm8)))),
+        Instruction::JrCondImm8(idx, imm8) =>
+            format!("jr {}, {}", cc(idx), target(next.wrapping_add_signed(signed(imm8)))),
+        Instruction::Stop => "stop".to_string(),
+        Instruction::LdR8R8(dst, src) => format!("ld {}, {}", r8(dst), r8(src)),
+        Instruction::Halt => "halt".to_string(),
+        Instruction::AluAR8(op, idx) => format!("{} a, {}", op.name().to_lowercase(), r8(idx)),
+        Instruction::AluAImm8(op, imm8) => format!("{} a, ${:02X}", op.name().to_lowercase(), imm8),
+        Instruction::RetCond(idx) => format!("ret {}", cc(idx)),
+        Instruction::Ret => "ret".to_string(),
+        Instruction::Reti => "reti".to_string(),
+        Instruction::JpCondImm16(idx, imm16) => format!("jp {}, {}", cc(idx), target(imm16)),
+        Instruction::JpImm16(imm16) => format!("jp {}", target(imm16)),
+        Instruction::JpHl => "jp hl".to_string(),
+        Instruction::CallCondImm16(idx, imm16) => format!("call {}, {}", cc(idx), target(imm16)),
+        Instruction::CallImm16(imm16) => format!("call {}", target(imm16)),
+        Instruction::Rst(tgt3) => format!("rst ${:02X}", tgt3 << 3),
+        Instruction::Pop(idx) => format!("pop {}", r16stk(idx)),
+        Instruction::Push(idx) => format!("push {}", r16stk(idx)),
+        Instruction::LdhCA => "ldh [c], a".to_string(),
+        Instruction::LdhImm8A(imm8) => format!("ldh [{}], a", target(0xFF00 | imm8 as u16)),
+        Instruction::LdImm16A(imm16) => format!("ld [{}], a", target(imm16)),
+        Instruction::LdhAC => "ldh a, [c]".to_string(),
+        Instruction::LdhAImm8(imm8) => format!("ldh a, [{}]", target(0xFF00 | imm8 as u16)),
+        Instruction::LdAImm16(imm16) => format!("ld a, [{}]", target(imm16)),
+        Instruction::AddSpImm8(imm8) => format!("add sp, {}", signed(imm8)),
+        Instruction::LdHlSpImm8(imm8) => match signed(imm8) {
+            e if e < 0 => format!("ld hl, sp - {}", -e),
+            e => format!("ld hl, sp + {}", e),
+        },
+        Instruction::LdSpHl => "ld sp, hl".to_string(),
+        Instruction::Di => "di".to_string(),
+        Instruction::Ei => "ei".to_string(),
+        // Not encodable, callers emit these as data
+        Instruction::Prefix => "db $CB".to_string(),
+        Instruction::Illegal(opcode) => format!("db ${:02X}", opcode),
+        Instruction::Shift(op, idx) => format!("{} {}", op.name().to_lowercase(), r8(idx)),
+        Instruction::Bit(b3, idx) => format!("bit {}, {}", b3, r8(idx)),
+        Instruction::Res(b3, idx) => format!("res {}, {}", b3, r8(idx)),
+        Instruction::Set(b3, idx) => format!("set {}, {}", b3, r8(idx)),
+    }
+}
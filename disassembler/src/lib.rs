@@ -0,0 +1,309 @@
+mod format;
+mod symbols;
+
+use std::fmt::Write;
+
+use constants::ROM1_BASE;
+use decoder::Instruction;
+
+pub use format::rgbds;
+pub use symbols::SymbolTable;
+
+const ROM_BANK_SIZE: usize = 0x4000;
+// Logo, title and the rest of the cartridge header (0x0104 - 0x014F) are data.
+const HEADER_DATA_BASE: u16 = 0x0104;
+const HEADER_DATA_END: u16 = 0x0150;
+const MAX_DATA_BYTES_PER_LINE: usize = 8;
+
+// A label, an instruction or a run of data bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    pub bank: usize,
+    pub addr: u16,
+    pub bytes: Vec<u8>,
+    pub text: String,
+}
+
+pub struct Disassembler<'a> {
+    rom: &'a [u8],
+    symbols: SymbolTable,
+}
+
+impl<'a> Disassembler<'a> {
+    pub fn new(rom: &'a [u8], symbols: SymbolTable) -> Disassembler<'a> {
+        Disassembler { rom, symbols }
+    }
+
+    pub fn banks(&self) -> usize {
+        self.rom.len().div_ceil(ROM_BANK_SIZE)
+    }
+
+    // Address range the bank is mapped to.
+    pub fn bank_range(&self, bank: usize) -> (u16, usize) {
+        let base: usize = if bank == 0 { 0 } else { ROM1_BASE };
+        let len: usize = ROM_BANK_SIZE.min(self.rom.len().saturating_sub(bank * ROM_BANK_SIZE));
+        (base as u16, base + len)
+    }
+
+    fn offset(bank: usize, addr: u16) -> usize {
+        bank * ROM_BANK_SIZE + (addr as usize % ROM_BANK_SIZE)
+    }
+
+    fn target(&self, bank: usize, addr: u16) -> String {
+        match self.symbols.get(bank, addr) {
+            Some(name) => name.to_string(),
+            None => format!("${:04X}", addr),
+        }
+    }
+
+    // True if a label points inside (addr, addr + len).
+    fn label_within(&self, bank: usize, addr: u16, len: usize) -> bool {
+        (1..len).any(|i| self.symbols.get(bank, addr.wrapping_add(i as u16)).is_some())
+    }
+
+    fn is_data(bank: usize, addr: u16) -> bool {
+        bank == 0 && (HEADER_DATA_BASE..HEADER_DATA_END).contains(&addr)
+    }
+
+    // Checks that `start..end` lies within where the bank is mapped.
+    pub fn check_range(&self, bank: usize, start: u16, end: usize) -> Result<(), String> {
+        if bank >= self.banks() {
+            return Err(format!("Bank {bank} is out of range, the rom has {} banks", self.banks()));
+        }
+        let (bank_start, bank_end) = self.bank_range(bank);
+        if start < bank_start || end > bank_end || start as usize > end {
+            return Err(format!(
+                "Range ${:04X}-${:04X} is outside of bank {bank}, which spans ${:04X}-${:04X}",
+                start, end, bank_start, bank_end,
+            ));
+        }
+        Ok(())
+    }
+
+    // Decodes `start..end` of the given bank. Whatever cannot be expressed as
+    // an instruction (illegal opcodes, instructions cut by the end of the
+    // range or by a label) is emitted as data.
+    pub fn decode_range(&self, bank: usize, start: u16, end: usize) -> Result<Vec<Line>, String> {
+        self.check_range(bank, start, end)?;
+        let mut lines: Vec<Line> = Vec::new();
+        let mut addr: usize = start as usize;
+
+        while addr < end {
+            let offset: usize = Disassembler::offset(bank, addr as u16);
+            let bytes: &[u8] = &self.rom[offset..offset + (end - addr)];
+            if let Some(name) = self.symbols.get(bank, addr as u16) {
+                lines.push(Line { bank, addr: addr as u16, bytes: Vec::new(), text: format!("{name}:") });
+            }
+
+            let decoded: Option<(Instruction, usize)> = match decoder::decode(bytes) {
+                _ if Disassembler::is_data(bank, addr as u16) => None,
+                Some((Instruction::Illegal(_), _)) => None,
+                // RGBDS always emits STOP as 0x10 0x00
+                Some((Instruction::Stop, _)) if bytes.get(1) == Some(&0x00) => Some((Instruction::Stop, 2)),
+                Some((Instruction::Stop, _)) => None,
+                Some((_, len)) if self.label_within(bank, addr as u16, len) => None,
+                other => other,
+            };
+
+            match decoded {
+                Some((instr, len)) => {
+                    let next: u16 = (addr + len) as u16;
+                    let text: String = rgbds(&instr, next, &|target| self.target(bank, target));
+                    lines.push(Line { bank, addr: addr as u16, bytes: bytes[..len].to_vec(), text });
+                    addr += len;
+                },
+                None => {
+                    let len: usize = self.data_len(bank, addr as u16, bytes.len());
+                    let text: String = bytes[..len].iter()
+                        .map(|b| format!("${:02X}", b))
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    lines.push(Line { bank, addr: addr as u16, bytes: bytes[..len].to_vec(), text: format!("db {text}") });
+                    addr += len;
+                },
+            }
+        }
+        Ok(lines)
+    }
+
+    // Header bytes are grouped, everything else is emitted one byte at a time
+    // so that decoding can resume right after.
+    fn data_len(&self, bank: usize, addr: u16, available: usize) -> usize {
+        let mut len: usize = 1;
+        while Disassembler::is_data(bank, addr + len as u16) && len < MAX_DATA_BYTES_PER_LINE && len < available
+            && self.symbols.get(bank, addr + len as u16).is_none() {
+            len += 1;
+        }
+        len
+    }
+
+    pub fn disassemble_range(&self, bank: usize, start: u16, end: usize) -> Result<String, String> {
+        let mut out: String = String::new();
+        for line in self.decode_range(bank, start, end)? {
+            if line.bytes.is_empty() {
+                writeln!(out, "{}", line.text).unwrap();
+            } else {
+                writeln!(out, "    {}", line.text).unwrap();
+            }
+        }
+        Ok(out)
+    }
+
+    // The whole ROM, one section per bank, with definitions for the labels
+    // that point outside of it so that the output assembles back with RGBDS.
+    pub fn disassemble_rom(&self) -> String {
+        let mut out: String = String::new();
+        for (addr, name) in self.symbols.ram_symbols() {
+            writeln!(out, "DEF {name} EQU ${:04X}", addr).unwrap();
+        }
+
+        for bank in 0..self.banks() {
+            let (start, end) = self.bank_range(bank);
+            if !out.is_empty() {
+                writeln!(out).unwrap();
+            }
+            if bank == 0 {
+                writeln!(out, "SECTION \"ROM Bank $000\", ROM0[$0000]").unwrap();
+            } else {
+                writeln!(out, "SECTION \"ROM Bank ${:03X}\", ROMX[$4000], BANK[${:03X}]", bank, bank).unwrap();
+            }
+            writeln!(out).unwrap();
+            out.push_str(&self.disassemble_range(bank, start, end).expect("Whole banks are valid ranges"));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two banks of NOPs with `code` at the start of each.
+    fn rom_with(bank0: &[u8], bank1: &[u8]) -> Vec<u8> {
+        let mut rom: Vec<u8> = vec![0; 2 * ROM_BANK_SIZE];
+        rom[..bank0.len()].copy_from_slice(bank0);
+        rom[ROM_BANK_SIZE..ROM_BANK_SIZE + bank1.len()].copy_from_slice(bank1);
+        rom
+    }
+
+    #[test]
+    fn ranges_are_checked_against_the_bank() {
+        let rom: Vec<u8> = rom_with(&[], &[]);
+        let disassembler: Disassembler = Disassembler::new(&rom, SymbolTable::new());
+        assert_eq!(disassembler.bank_range(0), (0x0000, 0x4000));
+        assert_eq!(disassembler.bank_range(1), (0x4000, 0x8000));
+        assert!(disassembler.check_range(1, 0x4000, 0x8000).is_ok());
+        assert!(disassembler.check_range(0, 0x0100, 0x0100).is_ok());
+
+        assert!(disassembler.decode_range(1, 0x2000, 0x8000).is_err());
+        assert!(disassembler.decode_range(1, 0x4000, 0x8001).is_err());
+        assert!(disassembler.decode_range(0, 0x3000, 0x4100).is_err());
+        assert!(disassembler.decode_range(0, 0x0200, 0x0100).is_err());
+        assert_eq!(disassembler.decode_range(2, 0x4000, 0x4100).unwrap_err(), "Bank 2 is out of range, the rom has 2 banks");
+    }
+
+    #[test]
+    fn decoding_a_range() {
+        // LD A, $12; JP $4000; illegal; STOP without its padding byte
+        let rom: Vec<u8> = rom_with(&[], &[0x3E, 0x12, 0xC3, 0x00, 0x40, 0xD3, 0x10]);
+        let disassembler: Disassembler = Disassembler::new(&rom, SymbolTable::new());
+        let lines: Vec<Line> = disassembler.decode_range(1, 0x4000, 0x4007).unwrap();
+        let text: Vec<(u16, &str)> = lines.iter().map(|line| (line.addr, line.text.as_str())).collect();
+        assert_eq!(text, [
+            (0x4000, "ld a, $12"),
+            (0x4002, "jp $4000"),
+            (0x4005, "db $D3"),
+            (0x4006, "db $10"),
+        ]);
+        assert_eq!(lines[1].bytes, [0xC3, 0x00, 0x40]);
+        assert_eq!(lines[1].bank, 1);
+
+        // An instruction cut by the end of the range is data
+        let lines: Vec<Line> = disassembler.decode_range(1, 0x4002, 0x4004).unwrap();
+        let text: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(text, ["db $C3", "nop"]);
+    }
+
+    #[test]
+    fn targets_are_bank_relative() {
+        // CALL $0150; CALL $4010 in both banks
+        let code: [u8; 6] = [0xCD, 0x50, 0x01, 0xCD, 0x10, 0x40];
+        let mut rom: Vec<u8> = rom_with(&code, &code);
+        rom.resize(3 * ROM_BANK_SIZE, 0);
+        rom[2 * ROM_BANK_SIZE..2 * ROM_BANK_SIZE + code.len()].copy_from_slice(&code);
+        let symbols: SymbolTable = SymbolTable::parse("00:0150 Home\n01:4010 Far\n00:C000 wVar\n").unwrap();
+        let disassembler: Disassembler = Disassembler::new(&rom, symbols);
+
+        let text = |bank: usize, start: u16| -> Vec<String> {
+            disassembler.decode_range(bank, start, start as usize + code.len()).unwrap()
+                .into_iter().map(|line| line.text).collect()
+        };
+        // Bank 0 labels are visible from everywhere, bank 1 ones only from
+        // bank 1; bank 0 has nothing mapped at $4010 on its own
+        assert_eq!(text(0, 0x0000), ["call Home", "call $4010"]);
+        assert_eq!(text(1, 0x4000), ["call Home", "call Far"]);
+        assert_eq!(text(2, 0x4000), ["call Home", "call $4010"]);
+        assert!(disassembler.disassemble_rom().starts_with("DEF wVar EQU $C000\n"));
+    }
+
+    // Symbol names RGBDS accepts, local ones only for labels.
+    // https://rgbds.gbdev.io/docs/rgbasm.5#Symbols
+    fn is_symbol_name(name: &str, local: bool) -> bool {
+        let mut parts = name.split('.');
+        let valid_part = |part: &str| part.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || "_#@$".contains(c));
+        parts.next().is_some_and(valid_part) && match (parts.next(), parts.next()) {
+            (None, _) => true,
+            (Some(child), None) => local && valid_part(child),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn output_symbols_round_trip() {
+        // CALL wBuffer.end; JP Main.loop
+        let rom: Vec<u8> = rom_with(&[0xCD, 0x10, 0xC0, 0xC3, 0x50, 0x01], &[]);
+        let text: &str = "00:0000 Main\n00:0150 Main.loop\n00:C000 wBuffer\n00:C010 wBuffer.end\n01:4000 Far\n";
+        let disassembler: Disassembler = Disassembler::new(&rom, SymbolTable::parse(text).unwrap());
+        let out: String = disassembler.disassemble_rom();
+        assert!(out.contains("DEF wBuffer_end EQU $C010\n"));
+        assert!(out.contains("    call wBuffer_end\n"));
+        assert!(out.contains("\nMain.loop:\n"));
+
+        // Everything defined in the output, back into a symbol file
+        let mut sym: String = String::new();
+        for line in out.lines() {
+            if let Some(def) = line.strip_prefix("DEF ") {
+                let (name, addr) = def.split_once(" EQU $").unwrap();
+                assert!(is_symbol_name(name, false), "{name}");
+                writeln!(sym, "00:{addr} {name}").unwrap();
+            }
+        }
+        for bank in 0..disassembler.banks() {
+            let (start, end) = disassembler.bank_range(bank);
+            for line in disassembler.decode_range(bank, start, end).unwrap() {
+                if let Some(name) = line.text.strip_suffix(':') {
+                    assert!(is_symbol_name(name, true), "{name}");
+                    writeln!(sym, "{:02X}:{:04X} {name}", line.bank, line.addr).unwrap();
+                }
+            }
+        }
+        let parsed: SymbolTable = SymbolTable::parse(&sym).unwrap();
+        for (bank, addr, name) in [(0, 0x0000, "Main"), (0, 0x0150, "Main.loop"), (1, 0x4000, "Far"), (0, 0xC010, "wBuffer_end")] {
+            assert_eq!(parsed.get(bank, addr), Some(name));
+            assert_eq!(disassembler.symbols.get(bank, addr), Some(name));
+        }
+        assert_eq!(sym.lines().count(), 5);
+    }
+
+    #[test]
+    fn mgb_boot_rom() {
+        let rom: &[u8] = include_bytes!("../../mgb.bin");
+        let symbols: SymbolTable = SymbolTable::parse(include_str!("../../mgb.sym")).unwrap();
+        let disassembler: Disassembler = Disassembler::new(rom, symbols);
+        let out: String = disassembler.disassemble_rom();
+        assert!(out.contains("\nEntryPoint:\n    ld sp, $FFFE\n"));
+        assert!(out.contains("DEF vBlankTile EQU $8000\n"));
+        assert!(out.contains("    jr nz, EntryPoint.clearVRAM\n"));
+    }
+}
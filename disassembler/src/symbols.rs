@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use constants::{ROM1_BASE, VRAM_BASE};
+use log::warn;
+
+// Boot ROM sections are mapped over the start of bank 0.
+const BOOT_BANK: &str = "BOOT";
+
+// Labels from an RGBDS .sym file, one "BB:AAAA name" entry per line.
+// https://rgbds.gbdev.io/sym/
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    rom: HashMap<(usize, u16), String>,
+    // Everything from 0x8000 up, looked up regardless of bank
+    ram: HashMap<u16, String>,
+}
+
+impl SymbolTable {
+    pub fn new() -> SymbolTable {
+        SymbolTable::default()
+    }
+
+    // Banks are hexadecimal numbers, or names for memory that has no bank
+    // number. Named banks other than the boot ROM are skipped.
+    pub fn parse(text: &str) -> Result<SymbolTable, String> {
+        let mut symbols: SymbolTable = SymbolTable::new();
+        for (i, line) in text.lines().enumerate() {
+            let line: &str = line.split(';').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let parsed = line.split_once(' ').and_then(|(loc, name)| {
+                let (bank, addr) = loc.split_once(':')?;
+                let addr: u16 = u16::from_str_radix(addr, 16).ok()?;
+                Some((bank, addr, name.trim()))
+            });
+            let Some((bank, addr, name)) = parsed else {
+                return Err(format!("Malformed symbol on line {}: {line}", i + 1));
+            };
+            match usize::from_str_radix(bank, 16) {
+                Ok(bank) => symbols.insert(bank, addr, name),
+                Err(_) if bank == BOOT_BANK => symbols.insert(0, addr, name),
+                Err(_) if !bank.is_empty() && bank.chars().all(|c| c.is_ascii_alphanumeric()) =>
+                    warn!("Skipping symbol {name} in unknown bank {bank} on line {}", i + 1),
+                Err(_) => return Err(format!("Malformed symbol on line {}: {line}", i + 1)),
+            }
+        }
+        Ok(symbols)
+    }
+
+    // The first label seen for an address wins. RAM labels end up as
+    // constants, which cannot be local, so "wBuffer.end" becomes
+    // "wBuffer_end".
+    pub fn insert(&mut self, bank: usize, addr: u16, name: &str) {
+        if addr as usize >= VRAM_BASE {
+            self.ram.entry(addr).or_insert_with(|| name.replace('.', "_"));
+        } else {
+            self.rom.entry((bank, addr)).or_insert_with(|| name.to_string());
+        }
+    }
+
+    pub fn get(&self, bank: usize, addr: u16) -> Option<&str> {
+        if addr as usize >= VRAM_BASE {
+            self.ram.get(&addr)
+        } else if (addr as usize) < ROM1_BASE {
+            self.rom.get(&(0, addr))
+        } else {
+            self.rom.get(&(bank, addr))
+        }.map(|s| s.as_str())
+    }
+
+    // Labels outside of ROM, sorted by address.
+    pub fn ram_symbols(&self) -> Vec<(u16, &str)> {
+        let mut symbols: Vec<(u16, &str)> = self.ram.iter().map(|(addr, name)| (*addr, name.as_str())).collect();
+        symbols.sort();
+        symbols
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn banks_and_addresses() {
+        let symbols: SymbolTable = SymbolTable::parse("; comment\n\n00:0150 Start ; trailing\n02:4000 Far\n00:C000 wVar\n03:D000 wBanked\n").unwrap();
+        assert_eq!(symbols.get(5, 0x0150), Some("Start"));
+        assert_eq!(symbols.get(2, 0x4000), Some("Far"));
+        assert_eq!(symbols.get(1, 0x4000), None);
+        // RAM labels are found from any bank
+        assert_eq!(symbols.get(7, 0xC000), Some("wVar"));
+        assert_eq!(symbols.get(0, 0xD000), Some("wBanked"));
+        assert_eq!(symbols.ram_symbols(), [(0xC000, "wVar"), (0xD000, "wBanked")]);
+    }
+
+    #[test]
+    fn first_label_wins() {
+        let symbols: SymbolTable = SymbolTable::parse("00:0150 Start\n00:0150 Start.alias\n").unwrap();
+        assert_eq!(symbols.get(0, 0x0150), Some("Start"));
+    }
+
+    #[test]
+    fn named_banks() {
+        let symbols: SymbolTable = SymbolTable::parse("BOOT:0007 Boot.loop\nSRAM:A000 sSave\n00:0100 Entry\n").unwrap();
+        assert_eq!(symbols.get(0, 0x0007), Some("Boot.loop"));
+        assert_eq!(symbols.get(0, 0xA000), None);
+        assert_eq!(symbols.get(0, 0x0100), Some("Entry"));
+    }
+
+    #[test]
+    fn local_ram_labels_are_promoted() {
+        let symbols: SymbolTable = SymbolTable::parse("00:C000 wBuffer\n00:C010 wBuffer.end\n00:0150 Main.loop\n").unwrap();
+        assert_eq!(symbols.get(0, 0xC010), Some("wBuffer_end"));
+        assert_eq!(symbols.get(0, 0x0150), Some("Main.loop"));
+    }
+
+    #[test]
+    fn malformed_lines() {
+        assert_eq!(SymbolTable::parse("00:0150\n").unwrap_err(), "Malformed symbol on line 1: 00:0150");
+        assert!(SymbolTable::parse("00:01G0 Start\n").is_err());
+        assert!(SymbolTable::parse("0150 Start\n").is_err());
+        assert!(SymbolTable::parse(":0150 Start\n").is_err());
+    }
+
+    #[test]
+    fn mgb_boot_rom_symbols() {
+        let symbols: SymbolTable = SymbolTable::parse(include_str!("../../mgb.sym")).unwrap();
+        assert_eq!(symbols.get(0, 0x0000), Some("EntryPoint"));
+        assert_eq!(symbols.get(0, 0x0007), Some("EntryPoint.clearVRAM"));
+        assert_eq!(symbols.get(0, 0x8000), Some("vBlankTile"));
+        assert_eq!(symbols.get(0, 0xFFFE), Some("hStackBottom"));
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::VecDeque;
+
+use crate::objects::{ObjPixel, Object, OBJ_X_OFFSET};
+use crate::{attr, lcdc, Ppu, DMG_RGB555, SCREEN_WIDTH, TILE_DATA_0, TILE_DATA_1, TILE_MAP_0, TILE_MAP_1};
+
+// The fetch of the first tile of a line is done twice, the first result is
+// thrown away.
+const DUMMY_FETCH_DOTS: u64 = 6;
+// Window pixels left of WX - 7 are off screen
+const WINDOW_X_OFFSET: u8 = 7;
+// With WX = 166 the window starts on the last pixel and covers all of the
+// following line.
+const WX_NEXT_LINE: u8 = 166;
+
+// A background or window pixel, with the attributes of its tile on CGB.
+#[derive(Clone, Copy)]
+struct BgPixel {
+    color: u8,
+    attrs: u8,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Tile,
+    DataLow,
+    DataHigh,
+    Push,
+}
+
+// Fetches 8 pixels of background or window at a time, every step but the
+// push takes 2 dots.
+// https://gbdev.io/pandocs/pixel_fifo.html
+struct Fetcher {
+    step: Step,
+    // Dots spent in the current step
+    dots: u8,
+    window: bool,
+    // Tile column, relative to SCX / 8 for the background
+    tile_x: u8,
+    tile_idx: u8,
+    // CGB map attributes, from VRAM bank 1
+    attrs: u8,
+    low: u8,
+    high: u8,
+}
+
+impl Fetcher {
+    fn new(window: bool) -> Fetcher {
+        Fetcher {
+            step: Step::Tile,
+            dots: 0,
+            window,
+            tile_x: 0,
+            tile_idx: 0,
+            attrs: 0,
+            low: 0,
+            high: 0,
+        }
+    }
+}
+
+// Mode 3 in progress: the pixel pipeline and how far along the line it is.
+pub(crate) struct Line {
+    fetcher: Fetcher,
+    fifo: VecDeque<BgPixel>,
+    obj_fifo: VecDeque<ObjPixel>,
+    // Next object of the OAM scan to reach
+    next_obj: usize,
+    // Last tile an object waited for, as (window, tile_x)
+    waited_tile: Option<(bool, u8)>,
+    // Pixels to throw away before the next one reaches the LCD
+    discard: u8,
+    // Dots left with the whole pipeline waiting, on the first tile fetch
+    // and on object fetches
+    stall: u64,
+    // Next pixel on the LCD
+    x: u8,
+    // See WX_NEXT_LINE
+    from_line_start: bool,
+    window_drawn: bool,
+}
+
+impl Line {
+    pub(crate) fn is_done(&self) -> bool {
+        self.x as usize == SCREEN_WIDTH
+    }
+}
+
+impl Ppu {
+    // Both the background and the window use LCDC bit 4 for tile data.
+    // https://gbdev.io/pandocs/Tile_Data.html
+    fn bg_tile_row(&self, tile_idx: u8, row: u8, attrs: u8) -> usize {
+        let tile: usize = if self.lcdc & lcdc::TILE_DATA != 0 {
+            TILE_DATA_0 + tile_idx as usize * 16
+        } else {
+            TILE_DATA_1.wrapping_add_signed(tile_idx as i8 as isize * 16)
+        };
+        let row: u8 = if attrs & attr::Y_FLIP != 0 { 7 - row } else { row };
+        tile + row as usize * 2
+    }
+
+    // Map position and row within the tile the fetcher reads from.
+    fn fetch_position(&self, fetcher: &Fetcher) -> (usize, u8) {
+        if fetcher.window {
+            let map: usize = if self.lcdc & lcdc::WINDOW_MAP != 0 { TILE_MAP_1 } else { TILE_MAP_0 };
+            let y: u8 = self.window_line;
+            (map + (y as usize / 8) * 32 + (fetcher.tile_x & 0x1F) as usize, y % 8)
+        } else {
+            let map: usize = if self.lcdc & lcdc::BG_MAP != 0 { TILE_MAP_1 } else { TILE_MAP_0 };
+            let y: u8 = self.ly.wrapping_add(self.scy);
+            let x: u8 = (self.scx / 8).wrapping_add(fetcher.tile_x) & 0x1F;
+            (map + (y as usize / 8) * 32 + x as usize, y % 8)
+        }
+    }
+
+    // Advances the fetcher by one dot.
+    fn tick_fetcher(&self, fetcher: &mut Fetcher, fifo: &mut VecDeque<BgPixel>) {
+        if fetcher.step == Step::Push {
+            // Only an empty FIFO takes new pixels
+            if fifo.is_empty() {
+                for i in 0..8 {
+                    let bit: u8 = if fetcher.attrs & attr::X_FLIP != 0 { i } else { 7 - i };
+                    fifo.push_back(BgPixel {
+                        color: (((fetcher.high >> bit) & 1) << 1) | ((fetcher.low >> bit) & 1),
+                        attrs: fetcher.attrs,
+                    });
+                }
+                fetcher.tile_x = fetcher.tile_x.wrapping_add(1);
+                fetcher.step = Step::Tile;
+            }
+            return;
+        }
+
+        fetcher.dots += 1;
+        if fetcher.dots < 2 {
+            return;
+        }
+        fetcher.dots = 0;
+
+        let (map_addr, row) = self.fetch_position(fetcher);
+        let bank: usize = ((fetcher.attrs & attr::BANK) >> 3) as usize;
+        let data_addr: usize = self.bg_tile_row(fetcher.tile_idx, row, fetcher.attrs);
+        fetcher.step = match fetcher.step {
+            Step::Tile => {
+                fetcher.tile_idx = self.vram(0, map_addr);
+                fetcher.attrs = if self.cgb { self.vram(1, map_addr) } else { 0 };
+                Step::DataLow
+            },
+            Step::DataLow => {
+                fetcher.low = self.vram(bank, data_addr);
+                Step::DataHigh
+            },
+            Step::DataHigh => {
+                fetcher.high = self.vram(bank, data_addr + 1);
+                Step::Push
+            },
+            Step::Push => Step::Push,
+        };
+    }
+
+    // Whether the window takes over before pixel `x` is pushed to the LCD.
+    fn window_starts_at(&self, x: u8, from_line_start: bool) -> bool {
+        if from_line_start || self.wx < WINDOW_X_OFFSET {
+            x == 0
+        } else {
+            x + WINDOW_X_OFFSET == self.wx
+        }
+    }
+
+    // Background or window pixel mixed with the object one on top of it,
+    // as the shade (DMG) or color number (CGB) and the 15-bit color.
+    // https://gbdev.io/pandocs/Tile_Data.html#layers
+    fn mix_pixel(&self, bg: BgPixel, obj: Option<ObjPixel>) -> (u8, u16) {
+        // Objects already fetched vanish as soon as LCDC bit 1 is cleared
+        let obj: Option<ObjPixel> = obj.filter(|_| self.lcdc & lcdc::OBJ_ENABLE != 0);
+        if self.cgb {
+            return self.mix_cgb_pixel(bg, obj);
+        }
+        // DMG: LCDC bit 0 blanks both the background and the window
+        let bg: u8 = if self.lcdc & lcdc::BG_ENABLE != 0 { bg.color } else { 0 };
+        let shade: u8 = match obj {
+            Some(obj) if obj.color != 0 && (obj.attrs & attr::PRIORITY == 0 || bg == 0) => {
+                let palette: u8 = if obj.attrs & attr::DMG_PALETTE != 0 { self.obp1 } else { self.obp0 };
+                Ppu::apply_palette(palette, obj.color)
+            },
+            _ => Ppu::apply_palette(self.bgp, bg),
+        };
+        (shade, DMG_RGB555[shade as usize])
+    }
+
+    // CGB: LCDC bit 0 takes priority away from the background and the
+    // window instead, otherwise either of the priority bits of the tile and
+    // the object puts colors 1 - 3 of the background in front.
+    // https://gbdev.io/pandocs/Tile_Maps.html#bg-to-obj-priority-in-cgb-mode
+    fn mix_cgb_pixel(&self, bg: BgPixel, obj: Option<ObjPixel>) -> (u8, u16) {
+        let bg_priority: bool = self.lcdc & lcdc::BG_ENABLE != 0 && bg.color != 0
+            && (bg.attrs | obj.map_or(0, |obj| obj.attrs)) & attr::PRIORITY != 0;
+        match obj {
+            Some(obj) if obj.color != 0 && !bg_priority => {
+                (obj.color, Ppu::cgb_color(&self.obj_palettes, obj.attrs & attr::CGB_PALETTE, obj.color))
+            },
+            _ => (bg.color, Ppu::cgb_color(&self.bg_palettes, bg.attrs & attr::CGB_PALETTE, bg.color)),
+        }
+    }
+
+    // Sets up mode 3 of the current line, as the OAM scan is over.
+    pub(crate) fn start_line(&mut self) {
+        if self.ly == self.wy {
+            self.wy_triggered = true;
+        }
+        self.line = Some(Line {
+            fetcher: Fetcher::new(false),
+            fifo: VecDeque::with_capacity(8),
+            obj_fifo: VecDeque::with_capacity(8),
+            next_obj: 0,
+            waited_tile: None,
+            // Only the fine scroll is latched, the coarse one is read by
+            // every tile fetch
+            discard: self.scx % 8,
+            stall: DUMMY_FETCH_DOTS,
+            x: 0,
+            from_line_start: std::mem::take(&mut self.window_from_line_start),
+            window_drawn: false,
+        });
+    }
+
+    // One dot of mode 3, with the registers as they are on that dot.
+    pub(crate) fn draw_dot(&mut self, line: &mut Line) {
+        if line.stall > 0 {
+            line.stall -= 1;
+            return;
+        }
+
+        let window_enabled: bool = self.lcdc & lcdc::WINDOW_ENABLE != 0 && self.wy_triggered;
+        // The window at WX < 7 folds the SCX discard into its own, anywhere
+        // else it only starts once the background discard is over.
+        let window_cut: bool = !line.from_line_start && self.wx < WINDOW_X_OFFSET;
+        if window_enabled && !line.fetcher.window && (line.discard == 0 || window_cut)
+            && self.window_starts_at(line.x, line.from_line_start) {
+            line.fetcher = Fetcher::new(true);
+            line.fifo.clear();
+            line.window_drawn = true;
+            if window_cut {
+                // The part of the window left of the screen is dropped.
+                // With WX = 0 the SCX fine scroll still applies on top,
+                // making the window jitter with SCX.
+                let scx_discard: u8 = if self.wx == 0 { line.discard } else { 0 };
+                line.discard = WINDOW_X_OFFSET - self.wx + scx_discard;
+            }
+        }
+
+        // Objects are fetched as their leftmost pixel, possibly off screen,
+        // is reached, stalling everything else. The FIFO holds what is left
+        // of the tile being shifted out, the fetcher pushes the next one
+        // when empty. Those reached with LCDC bit 1 clear are skipped for
+        // good, without a stall.
+        let mut penalty: u64 = 0;
+        while line.discard == 0 && line.next_obj < self.objects.len()
+            && self.objects[line.next_obj].x <= line.x + OBJ_X_OFFSET {
+            let obj: Object = self.objects[line.next_obj];
+            line.next_obj += 1;
+            if self.lcdc & lcdc::OBJ_ENABLE == 0 {
+                continue;
+            }
+            let (tile, offset): ((bool, u8), u8) = match line.fifo.len() {
+                0 => ((line.fetcher.window, line.fetcher.tile_x), 0),
+                len => ((line.fetcher.window, line.fetcher.tile_x.wrapping_sub(1)), 8 - len as u8),
+            };
+            penalty += Ppu::object_penalty(&obj, offset, line.waited_tile != Some(tile));
+            line.waited_tile = Some(tile);
+            self.fetch_object(&obj, &mut line.obj_fifo);
+        }
+        if penalty > 0 {
+            // This dot is the first of the stall
+            line.stall = penalty - 1;
+            return;
+        }
+
+        self.tick_fetcher(&mut line.fetcher, &mut line.fifo);
+        if let Some(pixel) = line.fifo.pop_front() {
+            if line.discard > 0 {
+                line.discard -= 1;
+            } else {
+                let (shade, color): (u8, u16) = self.mix_pixel(pixel, line.obj_fifo.pop_front());
+                self.put_pixel(line.x, self.ly, shade, color);
+                line.x += 1;
+            }
+        }
+    }
+
+    // Once the last pixel of the line is out.
+    pub(crate) fn end_line(&mut self, line: Line) {
+        // Only lines the window shows up on move its internal line counter
+        if line.window_drawn {
+            self.window_line += 1;
+        }
+        self.window_from_line_start = line.window_drawn && self.wx == WX_NEXT_LINE;
+    }
+}
@@ -1,208 +1,453 @@
-use std::{collections::VecDeque, sync::{Arc, Mutex, MutexGuard}, time::Duration};
+mod fetcher;
+mod objects;
 
-use constants::{IO_REGS_BASE, LCDC, LY, OAM_SIZE, SCX, SCY, VRAM_BASE, WX, WY};
-use sdl2::{event::Event, keyboard::Keycode, pixels::Color, rect::Rect, render::Canvas, video::Window, Sdl};
+use constants::intr;
+use fetcher::Line;
+use objects::{Object, OBJECTS_PER_LINE};
+use constants::{BCPD, BCPS, BGP, LCDC, LY, LYC, OAM_BASE, OAM_SIZE, OBP0, OBP1, OCPD, OCPS, SCX, SCY, STAT, VBK, VRAM_BASE, WX, WY};
 
-pub struct Ppu {
-    context: Sdl,
-    canvas: Canvas<Window>,
-    vram: Arc<Mutex<[u8; 0x2000]>>,
-    oam: Option<Arc<Mutex<[u8; 0x100]>>>,
-    io_regs: Arc<Mutex<[u8; 0x80]>>,
-
-    sprite_buffer: Vec<Sprite>,
-    fifo: VecDeque<u8>,
-    x_counter: u8,
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+// Per bank, CGB has two
+pub const VRAM_SIZE: usize = 0x2000;
+const VRAM_BANKS: usize = 2;
+pub const DOTS_PER_LINE: u64 = 456;
+// 144 visible lines and 10 of VBlank
+const LINES_PER_FRAME: u8 = 154;
+const OAM_SCAN_DOTS: u64 = 80;
+
+const STAT_LYC_EQUAL: u8 = 0b0000_0100;
+const STAT_HBLANK_INT: u8 = 0b0000_1000;
+const STAT_VBLANK_INT: u8 = 0b0001_0000;
+const STAT_OAM_INT: u8 = 0b0010_0000;
+const STAT_LYC_INT: u8 = 0b0100_0000;
+
+const TILE_MAP_0: usize = 0x9800;
+const TILE_MAP_1: usize = 0x9C00;
+const TILE_DATA_0: usize = 0x8000;
+// Tiles indexed as signed numbers from there
+const TILE_DATA_1: usize = 0x9000;
+
+// 8 palettes of 4 little endian 15-bit colors
+// https://gbdev.io/pandocs/Palettes.html#lcd-color-palettes-cgb-only
+const PALETTE_RAM_SIZE: usize = 64;
+const PALETTE_AUTO_INCREMENT: u8 = 0b1000_0000;
+const PALETTE_INDEX: u8 = 0b0011_1111;
+
+// https://gbdev.io/pandocs/LCDC.html
+pub mod lcdc {
+    pub const ENABLE: u8        = 0b1000_0000;
+    pub const WINDOW_MAP: u8    = 0b0100_0000;
+    pub const WINDOW_ENABLE: u8 = 0b0010_0000;
+    pub const TILE_DATA: u8     = 0b0001_0000;
+    pub const BG_MAP: u8        = 0b0000_1000;
+    pub const OBJ_SIZE: u8      = 0b0000_0100;
+    pub const OBJ_ENABLE: u8    = 0b0000_0010;
+    pub const BG_ENABLE: u8     = 0b0000_0001;
 }
 
-struct Sprite {
-    y_pos: u8,
-    x_pos: u8,
-    tile_idx: u8,
-    attrs: u8,
+// Object attributes. CGB background map attributes, in VRAM bank 1, share
+// the layout but for the DMG palette bit.
+// https://gbdev.io/pandocs/OAM.html#byte-3--attributes-flags
+// https://gbdev.io/pandocs/Tile_Maps.html#bg-map-attributes-cgb-mode-only
+mod attr {
+    pub const PRIORITY: u8      = 0b1000_0000;
+    pub const Y_FLIP: u8        = 0b0100_0000;
+    pub const X_FLIP: u8        = 0b0010_0000;
+    pub const DMG_PALETTE: u8   = 0b0001_0000;
+    pub const BANK: u8          = 0b0000_1000;
+    pub const CGB_PALETTE: u8   = 0b0000_0111;
 }
 
-impl Ppu {
-    pub fn new(vram: Arc<Mutex<[u8; 0x2000]>>, oam: Arc<Mutex<[u8; 0x100]>>, io_regs: Arc<Mutex<[u8; 0x80]>>) -> Ppu {
-        let sdl_context = sdl2::init().unwrap();
-        let video_subsystem = sdl_context.video().unwrap();
+// Values of the STAT mode bits
+// https://gbdev.io/pandocs/Rendering.html#ppu-modes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    HBlank = 0,
+    VBlank = 1,
+    OamScan = 2,
+    Drawing = 3,
+}
+
+// Shades 0 - 3 as RGBA, from lightest to darkest
+static DMG_COLORS: [[u8; 4]; 4] = [
+    [0xFF, 0xFF, 0xFF, 0xFF],
+    [0xAA, 0xAA, 0xAA, 0xFF],
+    [0x55, 0x55, 0x55, 0xFF],
+    [0x00, 0x00, 0x00, 0xFF],
+];
+
+// The same shades as 15-bit colors
+static DMG_RGB555: [u16; 4] = [0x7FFF, 0x56B5, 0x294A, 0x0000];
+
+// Renders into a framebuffer of shades, and its RGBA version, without
+// knowing anything about how or whether it gets displayed. While the LCD is
+// on the console calls `advance_mode` whenever the current mode is over,
+// `mode_dots` after entering it, except for mode 3 which is drawn dot by dot
+// through `tick` until it says it is over.
+// https://gbdev.io/pandocs/Rendering.html
+pub struct Ppu {
+    // Running a CGB title, with its registers and attributes
+    cgb: bool,
+    vram: [[u8; VRAM_SIZE]; VRAM_BANKS],
+    oam: [u8; OAM_SIZE],
+
+    lcdc: u8,
+    stat: u8,
+    scy: u8,
+    scx: u8,
+    ly: u8,
+    lyc: u8,
+    bgp: u8,
+    obp0: u8,
+    obp1: u8,
+    wy: u8,
+    wx: u8,
+    // VRAM bank the CPU sees
+    vbk: u8,
+    bcps: u8,
+    ocps: u8,
+    bg_palettes: [u8; PALETTE_RAM_SIZE],
+    obj_palettes: [u8; PALETTE_RAM_SIZE],
+
+    mode: Mode,
+    mode_dots: u64,
+    // The first line after the LCD is turned on has no OAM scan
+    first_line: bool,
+    // OR of the enabled STAT sources, interrupts fire on its rising edge
+    stat_line: bool,
+    // Interrupts raised since the console last asked
+    interrupts: u8,
+
+    // Set once LY matched WY in the current frame
+    wy_triggered: bool,
+    // Window line to fetch, only counts lines the window was drawn on
+    window_line: u8,
+    // See WX_NEXT_LINE
+    window_from_line_start: bool,
+    // Objects found by the OAM scan of the current line
+    objects: Vec<Object>,
+    // Mode 3 of the current line, while it is being drawn
+    line: Option<Line>,
 
-        let window = video_subsystem.window("gameboy demo", 800, 720)
-        .position_centered()
-        .build()
-        .unwrap();
+    // Shades on DMG, color numbers before the palettes on CGB
+    shades: Box<[u8; SCREEN_WIDTH * SCREEN_HEIGHT]>,
+    colors: Box<[u16; SCREEN_WIDTH * SCREEN_HEIGHT]>,
+    rgba: Box<[u8; SCREEN_WIDTH * SCREEN_HEIGHT * 4]>,
+    // Frames completed since power on
+    frames: u64,
+}
 
-        let canvas = window.into_canvas().build().unwrap();
+impl Ppu {
+    pub fn new(cgb: bool) -> Ppu {
         Ppu {
-            context: sdl_context,
-            canvas: canvas,
-            vram: vram,
-            oam: Some(oam),
-            io_regs: io_regs,
-            sprite_buffer: Vec::new(),
-            fifo: VecDeque::new(),
-            x_counter: 0,
+            cgb,
+            vram: [[0; VRAM_SIZE]; VRAM_BANKS],
+            oam: [0; OAM_SIZE],
+            lcdc: 0,
+            stat: 0,
+            scy: 0,
+            scx: 0,
+            ly: 0,
+            lyc: 0,
+            bgp: 0,
+            obp0: 0,
+            obp1: 0,
+            wy: 0,
+            wx: 0,
+            vbk: 0,
+            bcps: 0,
+            ocps: 0,
+            // The boot ROM leaves the background palettes white
+            bg_palettes: [0xFF; PALETTE_RAM_SIZE],
+            obj_palettes: [0; PALETTE_RAM_SIZE],
+            mode: Mode::HBlank,
+            mode_dots: 0,
+            first_line: false,
+            stat_line: false,
+            interrupts: 0,
+            wy_triggered: false,
+            window_line: 0,
+            window_from_line_start: false,
+            objects: Vec::with_capacity(OBJECTS_PER_LINE),
+            line: None,
+            shades: Box::new([0; SCREEN_WIDTH * SCREEN_HEIGHT]),
+            colors: Box::new([0x7FFF; SCREEN_WIDTH * SCREEN_HEIGHT]),
+            rgba: Box::new([0xFF; SCREEN_WIDTH * SCREEN_HEIGHT * 4]),
+            frames: 0,
         }
     }
 
-    fn get_color(low: u8, high: u8, idx: i32) -> u8 {
-        let high_bit: u8 = (high & (1 << 7) >> idx) >> 7 - idx;
-        let low_bit: u8 = (low & (1 << 7) >> idx) >> 7 - idx;
-        (high_bit << 1) | low_bit
+    // Shade (0 - 3) of every pixel, after the palettes are applied. CGB
+    // titles get the color number instead, see `colors`.
+    pub fn framebuffer(&self) -> &[u8; SCREEN_WIDTH * SCREEN_HEIGHT] {
+        &self.shades
     }
-    
-    fn draw_pixel(canvas: &mut Canvas<Window>, io_regs_guard: &MutexGuard<'_, [u8; 0x80]>, idx: u8, color: u8) {
-        let ly: u8 = io_regs_guard[LY - IO_REGS_BASE];
-        let px: Rect = Rect::new((idx as u16 * 5).into(), ly.into(),5, 5);
 
-        let color_palette: [u8; 4] = [0, 1, 2, 3];
-        let rgb_val: u8 = 255 - color_palette[color as usize] * 85;
+    // 15-bit color of every pixel, red in the low bits.
+    pub fn colors(&self) -> &[u16; SCREEN_WIDTH * SCREEN_HEIGHT] {
+        &self.colors
+    }
 
-        canvas.set_draw_color(Color::RGB(rgb_val, rgb_val, rgb_val));
-        canvas.draw_rect(px).unwrap();
-        canvas.fill_rect(px).unwrap();
+    pub fn rgba(&self) -> &[u8; SCREEN_WIDTH * SCREEN_HEIGHT * 4] {
+        &self.rgba
     }
 
-    fn mode2(&mut self, oam_guard: &MutexGuard<'_, [u8; 0x100]>) {
-        let ly: u8 = self.io_regs.lock().unwrap()[LY - IO_REGS_BASE];
+    // What the LCD shows while the system is stopped: white.
+    pub fn blank(&mut self) {
+        self.shades.fill(0);
+        self.colors.fill(0x7FFF);
+        self.rgba.fill(0xFF);
+    }
 
-        let mut counter: usize = 0;
-        while counter < OAM_SIZE {
-            let y_pos: u8 = oam_guard[counter];
-            let x_pos: u8 = oam_guard[counter + 1];
-            let tile_idx: u8 = oam_guard[counter + 2];
-            let attrs: u8 = oam_guard[counter + 3];
-            let sprite_heights = if self.io_regs.lock().unwrap()[LCDC - IO_REGS_BASE] & (1 << 2) == 0 { 0 } else { 1 };
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
 
-            if ly + 16 >= y_pos && ly + 16 < y_pos + sprite_heights && self.sprite_buffer.len() < 10 {
-                self.sprite_buffer.push(Sprite {
-                    y_pos: y_pos,
-                    x_pos: x_pos,
-                    tile_idx: tile_idx,
-                    attrs: attrs,
-                });
-            }
+    pub fn lcd_enabled(&self) -> bool {
+        self.lcdc & lcdc::ENABLE != 0
+    }
 
-            counter += 4;
-        }
+    pub fn mode(&self) -> Mode {
+        self.mode
     }
 
-    fn fetch_tile(_oam_guard: &MutexGuard<'_,[u8; 0x100]>,
-                            vram_guard: &MutexGuard<'_, [u8; 0x2000]>,
-                            io_regs_guard: &MutexGuard<'_, [u8; 0x80]>,
-                            pixel_idx: u8, fifo: &mut VecDeque<u8>) {
-        let tile_base_addr: u16 = 0x9800;
-        let lcdc: u8 = io_regs_guard[LCDC - IO_REGS_BASE];
-        let scx: u8 = io_regs_guard[SCX - IO_REGS_BASE];
-        let scy: u8 = io_regs_guard[SCY - IO_REGS_BASE];
+    // Length of the current mode, for mode 3 how long it has lasted so far
+    pub fn mode_dots(&self) -> u64 {
+        self.mode_dots
+    }
 
-        let ly: u8 = io_regs_guard[LY - IO_REGS_BASE];
+    // VBlank and STAT interrupts raised since the last call.
+    pub fn take_interrupts(&mut self) -> u8 {
+        std::mem::take(&mut self.interrupts)
+    }
 
-        // Skipping Window logic for now
-        let curr_tile: u16 = (((pixel_idx + (scx / 8)) & 0x1F).wrapping_add(32u8.wrapping_mul(((ly.wrapping_add(scy)) & 0xFF) / 8))).into();
-        let tile_idx: u8 = vram_guard[(tile_base_addr + curr_tile) as usize - VRAM_BASE];
+    // The CPU cannot reach VRAM while it is being drawn from, nor OAM while
+    // it is scanned or drawn from.
+    // https://gbdev.io/pandocs/Rendering.html#ppu-modes
+    pub fn vram_accessible(&self) -> bool {
+        !self.lcd_enabled() || self.mode != Mode::Drawing
+    }
 
-        let tdl: u8 = vram_guard[tile_idx as usize];
-        let tdh: u8 = vram_guard[tile_idx as usize];
+    pub fn oam_accessible(&self) -> bool {
+        !self.lcd_enabled() || !matches!(self.mode, Mode::OamScan | Mode::Drawing)
+    }
 
-        for i in 0..8 {
-            let px: u8 = Ppu::get_color(tdl, tdh, i);
-            fifo.push_back(px); 
-        }
+    fn vram(&self, bank: usize, addr: usize) -> u8 {
+        self.vram[bank][addr - VRAM_BASE]
     }
 
-    fn mode3(&mut self, oam_guard: &MutexGuard<'_, [u8; 0x100]>) {
-        let ly: u8 = oam_guard[LY - IO_REGS_BASE];
-        let vram_guard = self.vram.lock().unwrap();
-        let io_regs_guard = self.io_regs.lock().unwrap();
-
-        let mut drawn_pixels: u8 = 0;
-        let mut fetched_tiles: u8 = 0;
-        while drawn_pixels < 160 {
-            if self.fifo.len() > 8 {
-                let px: u8 = self.fifo.pop_front().unwrap();
-                Ppu::draw_pixel(&mut self.canvas, &io_regs_guard, drawn_pixels, px);
-                drawn_pixels += 1;
-            } else {
-                // fetch new tile and update queue
-                Ppu::fetch_tile(&oam_guard, &vram_guard, &io_regs_guard, fetched_tiles, &mut self.fifo);
-                fetched_tiles += 1;
-            }
-        }
+    // Through the bank selected by VBK
+    pub fn read_vram(&self, addr: usize) -> u8 {
+        self.vram(self.vbk as usize, addr)
     }
 
-    fn mode0(&mut self) {
-        self.sprite_buffer.clear();
-        self.fifo.clear();
-        //::std::thread::sleep(Duration::from_millis(500));
+    pub fn write_vram(&mut self, addr: usize, val: u8) {
+        self.vram[self.vbk as usize][addr - VRAM_BASE] = val;
     }
 
-    fn mode1() {
-        ::std::thread::sleep(Duration::from_millis(1));
+    pub fn read_oam(&self, addr: usize) -> u8 {
+        self.oam[addr - OAM_BASE]
     }
 
-    pub fn execute(&mut self) {
-        self.canvas.set_draw_color(Color::RGB(0, 0, 0));
-        self.canvas.clear();
-        self.canvas.present();
-        let mut event_pump = self.context.event_pump().unwrap();
-        'running: loop {
-            for event in event_pump.poll_iter() {
-                match event {
-                    Event::Quit {..} |
-                    Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
-                        break 'running
-                    },
-                    _ => {}
-                }
-            }
+    pub fn write_oam(&mut self, addr: usize, val: u8) {
+        self.oam[addr - OAM_BASE] = val;
+    }
 
-            self.draw_frame();
-            self.canvas.present();
-            ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    pub fn read(&self, addr: usize) -> u8 {
+        match addr {
+            LCDC => self.lcdc,
+            STAT => {
+                let lyc_equal: u8 = if self.ly == self.lyc { STAT_LYC_EQUAL } else { 0 };
+                let mode: u8 = if self.lcd_enabled() { self.mode as u8 } else { 0 };
+                0x80 | self.stat | lyc_equal | mode
+            },
+            SCY => self.scy,
+            SCX => self.scx,
+            LY => self.ly,
+            LYC => self.lyc,
+            BGP => self.bgp,
+            OBP0 => self.obp0,
+            OBP1 => self.obp1,
+            WY => self.wy,
+            WX => self.wx,
+            VBK if self.cgb => 0xFE | self.vbk,
+            BCPS if self.cgb => 0x40 | self.bcps,
+            OCPS if self.cgb => 0x40 | self.ocps,
+            // Palette RAM is out of reach while it is being drawn from
+            BCPD | OCPD if self.cgb && !self.vram_accessible() => 0xFF,
+            BCPD if self.cgb => self.bg_palettes[(self.bcps & PALETTE_INDEX) as usize],
+            OCPD if self.cgb => self.obj_palettes[(self.ocps & PALETTE_INDEX) as usize],
+            _ => panic!("Invalid PPU register"),
         }
     }
 
-    fn draw_tile(&mut self, color_palette: &[u8; 4], tile: &[u8; 16], tile_x: usize, tile_y: usize) {
-        for i in 0..8i32 {
-            for j in 0..8i32 {
-                let color: u8 = Ppu::get_color(tile[(2*i) as usize], tile[(2*i+1) as usize], j);
-                let pixel: Rect = Rect::new((tile_x*40) as i32+j*5, (tile_y*40) as i32+i*5, 5, 5);
-
-                let rgb_val: u8 = 255 - color_palette[color as usize] * 85;
-                self.canvas.set_draw_color(Color::RGB(1, 0, 0));
-                self.canvas.draw_rect(pixel).unwrap();
-                self.canvas.set_draw_color(Color::RGB(rgb_val, rgb_val, rgb_val));
-                self.canvas.fill_rect(pixel).unwrap();
-            }
+    pub fn write(&mut self, addr: usize, val: u8) {
+        match addr {
+            LCDC => {
+                let was_enabled: bool = self.lcd_enabled();
+                self.lcdc = val;
+                if was_enabled != self.lcd_enabled() {
+                    // Either way the LCD restarts from the top of the screen,
+                    // in a mode 0 that stands in for the first OAM scan.
+                    self.ly = 0;
+                    self.mode = Mode::HBlank;
+                    self.mode_dots = OAM_SCAN_DOTS;
+                    self.first_line = true;
+                    self.objects.clear();
+                    self.line = None;
+                    self.start_frame();
+                }
+            },
+            // Only the interrupt selection bits are writable
+            STAT => self.stat = val & 0x78,
+            SCY => self.scy = val,
+            SCX => self.scx = val,
+            LY => (),
+            LYC => self.lyc = val,
+            BGP => self.bgp = val,
+            OBP0 => self.obp0 = val,
+            OBP1 => self.obp1 = val,
+            WY => self.wy = val,
+            WX => self.wx = val,
+            VBK if self.cgb => self.vbk = val & 1,
+            BCPS if self.cgb => self.bcps = val & !0x40,
+            OCPS if self.cgb => self.ocps = val & !0x40,
+            BCPD if self.cgb => {
+                let accessible: bool = self.vram_accessible();
+                Ppu::write_palette(&mut self.bg_palettes, &mut self.bcps, val, accessible);
+            },
+            OCPD if self.cgb => {
+                let accessible: bool = self.vram_accessible();
+                Ppu::write_palette(&mut self.obj_palettes, &mut self.ocps, val, accessible);
+            },
+            _ => panic!("Invalid PPU register"),
         }
+        self.update_stat_line(false);
     }
 
-    fn draw_frame(&mut self) {
-        for i in 0..144 {
-            self.io_regs.lock().unwrap()[LY - IO_REGS_BASE] = i;
-            let oam = self.oam.take();
-            let oam_mutex = oam.unwrap();
-            {
-                let oam_guard = oam_mutex.lock().unwrap();
-                self.mode2(&oam_guard);
-                self.mode3(&oam_guard);
+    // Moves on to the next mode once the current one is over and returns
+    // how many dots the new one lasts. Mode 3 lasts until `tick` is done
+    // with it, 0 stands in for its length until then.
+    pub fn advance_mode(&mut self) -> u64 {
+        let (mode, dots): (Mode, u64) = match self.mode {
+            Mode::OamScan => {
+                self.start_line();
+                (Mode::Drawing, 0)
+            },
+            Mode::Drawing => (Mode::HBlank, DOTS_PER_LINE - OAM_SCAN_DOTS - self.mode_dots),
+            Mode::HBlank if self.first_line => {
+                self.first_line = false;
+                self.start_line();
+                (Mode::Drawing, 0)
+            },
+            Mode::HBlank => {
+                self.ly += 1;
+                if self.ly as usize == SCREEN_HEIGHT {
+                    self.frames += 1;
+                    self.interrupts |= intr::VBLANK;
+                    (Mode::VBlank, DOTS_PER_LINE)
+                } else {
+                    self.scan_oam();
+                    (Mode::OamScan, OAM_SCAN_DOTS)
+                }
+            },
+            Mode::VBlank => {
+                self.ly = (self.ly + 1) % LINES_PER_FRAME;
+                if self.ly == 0 {
+                    self.start_frame();
+                    self.scan_oam();
+                    (Mode::OamScan, OAM_SCAN_DOTS)
+                } else {
+                    (Mode::VBlank, DOTS_PER_LINE)
+                }
+            },
+        };
+        // The mode 2 source also fires as VBlank starts
+        let entering_vblank: bool = mode == Mode::VBlank && self.mode != Mode::VBlank;
+        self.mode = mode;
+        self.mode_dots = dots;
+        self.update_stat_line(entering_vblank);
+        dots
+    }
+
+    // Draws `dots` more dots of mode 3, so that registers written in the
+    // middle of it take effect from the next pixel on. Once the line is over
+    // the PPU moves on to HBlank and this returns how many dots of it are
+    // left after the `dots` given.
+    // https://gbdev.io/pandocs/pixel_fifo.html
+    pub fn tick(&mut self, dots: u64) -> Option<u64> {
+        let mut line: Line = self.line.take()?;
+        for i in 1..=dots {
+            self.draw_dot(&mut line);
+            self.mode_dots += 1;
+            if line.is_done() {
+                self.end_line(line);
+                return Some(self.advance_mode() - (dots - i));
             }
-            self.oam = Some(oam_mutex);
-            self.mode0();
         }
-        for i in 0..10 {
-            self.io_regs.lock().unwrap()[LY - IO_REGS_BASE] = 144 + i;
-            Ppu::mode1();
+        self.line = Some(line);
+        None
+    }
+
+    fn start_frame(&mut self) {
+        self.wy_triggered = false;
+        self.window_line = 0;
+        self.window_from_line_start = false;
+    }
+
+    // https://gbdev.io/pandocs/STAT.html
+    fn update_stat_line(&mut self, entering_vblank: bool) {
+        let line: bool = self.lcd_enabled() && (
+            (self.ly == self.lyc && self.stat & STAT_LYC_INT != 0)
+            || (self.mode == Mode::HBlank && self.stat & STAT_HBLANK_INT != 0)
+            || (self.mode == Mode::VBlank && self.stat & STAT_VBLANK_INT != 0)
+            || ((self.mode == Mode::OamScan || entering_vblank) && self.stat & STAT_OAM_INT != 0)
+        );
+        if line && !self.stat_line {
+            self.interrupts |= intr::LCD;
         }
+        self.stat_line = line;
     }
 
-    /*fn draw_frame(&mut self, tile_data: [[u8; 16]; 256], tile_map: [u8; 360]) {
-        for i in 0..18usize {
-            for j in 0..20usize {
-                let tile: &[u8; 16] = &tile_data[tile_map[i*20 + j] as usize];
-                self.draw_tile(&[0, 1, 2, 3], tile, j, i);
-            }
+    // https://gbdev.io/pandocs/Palettes.html
+    fn apply_palette(palette: u8, color: u8) -> u8 {
+        (palette >> (color * 2)) & 0x03
+    }
+
+    // Writes go through even while palette RAM is unreachable, the index
+    // still moves on.
+    fn write_palette(ram: &mut [u8; PALETTE_RAM_SIZE], spec: &mut u8, val: u8, accessible: bool) {
+        if accessible {
+            ram[(*spec & PALETTE_INDEX) as usize] = val;
         }
-    }*/
-}
\ No newline at end of file
+        if *spec & PALETTE_AUTO_INCREMENT != 0 {
+            *spec = PALETTE_AUTO_INCREMENT | ((*spec + 1) & PALETTE_INDEX);
+        }
+    }
+
+    fn cgb_color(ram: &[u8; PALETTE_RAM_SIZE], palette: u8, color: u8) -> u16 {
+        let idx: usize = palette as usize * 8 + color as usize * 2;
+        u16::from_le_bytes([ram[idx], ram[idx + 1]]) & 0x7FFF
+    }
+
+    fn put_pixel(&mut self, x: u8, y: u8, shade: u8, color: u16) {
+        let idx: usize = y as usize * SCREEN_WIDTH + x as usize;
+        self.shades[idx] = shade;
+        self.colors[idx] = color;
+        let rgba: [u8; 4] = if self.cgb {
+            // 5 bits per channel scaled to 8, no color correction
+            let [r, g, b]: [u8; 3] = [0, 5, 10].map(|shift| ((color >> shift) & 0x1F) as u8);
+            [r << 3 | r >> 2, g << 3 | g >> 2, b << 3 | b >> 2, 0xFF]
+        } else {
+            DMG_COLORS[shade as usize]
+        };
+        self.rgba[idx * 4..idx * 4 + 4].copy_from_slice(&rgba);
+    }
+}
+
+impl Default for Ppu {
+    fn default() -> Self {
+        Ppu::new(false)
+    }
+}
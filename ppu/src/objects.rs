@@ -0,0 +1,113 @@
+use std::collections::VecDeque;
+
+use crate::{attr, lcdc, Ppu, TILE_DATA_0};
+
+pub(crate) const OBJECTS_PER_LINE: usize = 10;
+const OBJECT_COUNT: usize = 40;
+// OAM Y and X are offset so that objects can be partially off screen
+const OBJ_Y_OFFSET: u8 = 16;
+pub(crate) const OBJ_X_OFFSET: u8 = 8;
+// Fetching the object tile itself
+const OBJ_FETCH_DOTS: u64 = 6;
+// Objects at X = 0 are hidden but still stall the fetcher
+const OBJ_X0_DOTS: u64 = 11;
+
+#[derive(Clone, Copy)]
+pub(crate) struct Object {
+    y: u8,
+    pub(crate) x: u8,
+    tile: u8,
+    attrs: u8,
+    // Position in OAM
+    index: u8,
+}
+
+// An object pixel waiting to be mixed with the background.
+#[derive(Clone, Copy)]
+pub(crate) struct ObjPixel {
+    pub(crate) color: u8,
+    pub(crate) attrs: u8,
+    index: u8,
+}
+
+impl Ppu {
+    fn obj_height(&self) -> u8 {
+        if self.lcdc & lcdc::OBJ_SIZE != 0 { 16 } else { 8 }
+    }
+
+    // Mode 2: picks the first 10 objects of OAM that overlap the line,
+    // objects off screen horizontally included.
+    // https://gbdev.io/pandocs/OAM.html#selection-priority
+    pub(crate) fn scan_oam(&mut self) {
+        let height: u8 = self.obj_height();
+        let line: u16 = (self.ly + OBJ_Y_OFFSET) as u16;
+        self.objects.clear();
+        for i in 0..OBJECT_COUNT {
+            let y: u8 = self.oam[i * 4];
+            if (y as u16..y as u16 + height as u16).contains(&line) {
+                self.objects.push(Object {
+                    y,
+                    x: self.oam[i * 4 + 1],
+                    tile: self.oam[i * 4 + 2],
+                    attrs: self.oam[i * 4 + 3],
+                    index: i as u8,
+                });
+                if self.objects.len() == OBJECTS_PER_LINE {
+                    break;
+                }
+            }
+        }
+        // Objects are fetched from left to right. On DMG the leftmost one
+        // also wins, then the first one in OAM, which a stable sort keeps.
+        self.objects.sort_by_key(|obj| obj.x);
+    }
+
+    // Dots mode 3 is stalled by an object. `offset` is the position of its
+    // leftmost pixel within the background or window tile being shifted out,
+    // waiting for that tile is paid for only by the first object in it.
+    // https://gbdev.io/pandocs/Rendering.html#obj-penalty-algorithm
+    pub(crate) fn object_penalty(obj: &Object, offset: u8, first_in_tile: bool) -> u64 {
+        if obj.x == 0 {
+            return OBJ_X0_DOTS;
+        }
+        let wait: u64 = if first_in_tile { 5u64.saturating_sub(offset as u64) } else { 0 };
+        wait + OBJ_FETCH_DOTS
+    }
+
+    // Fetches the row of the object on the current line and merges it into
+    // the object FIFO. On DMG pixels already there belong to objects with
+    // priority and are only replaced where transparent, on CGB the first
+    // object in OAM wins.
+    pub(crate) fn fetch_object(&self, obj: &Object, fifo: &mut VecDeque<ObjPixel>) {
+        let height: u8 = self.obj_height();
+        // The size can have changed since the scan
+        let mut row: u8 = (self.ly + OBJ_Y_OFFSET - obj.y) % height;
+        if obj.attrs & attr::Y_FLIP != 0 {
+            row = height - 1 - row;
+        }
+        // In 8x16 mode the top tile is always even
+        let tile: u8 = if height == 16 { obj.tile & 0xFE } else { obj.tile };
+        let addr: usize = TILE_DATA_0 + tile as usize * 16 + row as usize * 2;
+        let bank: usize = if self.cgb { ((obj.attrs & attr::BANK) >> 3) as usize } else { 0 };
+        let low: u8 = self.vram(bank, addr);
+        let high: u8 = self.vram(bank, addr + 1);
+
+        // Left part of objects at X < 8 is off screen
+        let hidden: u8 = OBJ_X_OFFSET.saturating_sub(obj.x);
+        for i in hidden..8 {
+            let bit: u8 = if obj.attrs & attr::X_FLIP != 0 { i } else { 7 - i };
+            let pixel: ObjPixel = ObjPixel {
+                color: (((high >> bit) & 1) << 1) | ((low >> bit) & 1),
+                attrs: obj.attrs,
+                index: obj.index,
+            };
+            let slot: usize = (i - hidden) as usize;
+            match fifo.get_mut(slot) {
+                Some(old) if old.color == 0 => *old = pixel,
+                Some(old) if self.cgb && pixel.color != 0 && pixel.index < old.index => *old = pixel,
+                Some(_) => (),
+                None => fifo.push_back(pixel),
+            }
+        }
+    }
+}
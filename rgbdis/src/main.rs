@@ -0,0 +1,133 @@
+use std::env;
+use std::fs::{read, read_to_string};
+use std::process::ExitCode;
+
+use disassembler::{Disassembler, SymbolTable};
+use env_logger::Env;
+
+const USAGE: &str = "Usage: rgbdis [--sym <file.sym>] [--range <BB:SSSS-EEEE>] <rom>";
+
+// "BB:SSSS-EEEE", all hexadecimal, end exclusive
+fn parse_range(range: &str) -> Option<(usize, u16, usize)> {
+    let (bank, addrs) = range.split_once(':')?;
+    let (start, end) = addrs.split_once('-')?;
+    Some((
+        usize::from_str_radix(bank, 16).ok()?,
+        u16::from_str_radix(start, 16).ok()?,
+        usize::from_str_radix(end, 16).ok()?,
+    ))
+}
+
+struct Args {
+    sym_filename: Option<String>,
+    range: Option<(usize, u16, usize)>,
+    filename: String,
+}
+
+fn parse_args(args: &[String]) -> Option<Args> {
+    let mut sym_filename: Option<String> = None;
+    let mut range: Option<(usize, u16, usize)> = None;
+    let mut filename: Option<String> = None;
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--sym" => sym_filename = Some(it.next()?.clone()),
+            "--range" => range = Some(parse_range(it.next()?)?),
+            _ if filename.is_none() => filename = Some(arg.clone()),
+            _ => return None,
+        }
+    }
+    Some(Args { sym_filename, range, filename: filename? })
+}
+
+enum Error {
+    // Bad command line, reported along with the usage
+    Usage(String),
+    Failure(String),
+}
+
+fn run(args: Args) -> Result<String, Error> {
+    let rom: Vec<u8> = read(&args.filename).map_err(|err| Error::Failure(format!("Failed to read the rom: {err}")))?;
+    let symbols: SymbolTable = match args.sym_filename {
+        Some(f) => {
+            let text: String = read_to_string(f)
+                .map_err(|err| Error::Failure(format!("Failed to read the symbol file: {err}")))?;
+            SymbolTable::parse(&text).map_err(|err| Error::Failure(format!("Failed to load symbols: {err}")))?
+        },
+        None => SymbolTable::new(),
+    };
+
+    let disassembler: Disassembler = Disassembler::new(&rom, symbols);
+    match args.range {
+        Some((bank, start, end)) => disassembler.disassemble_range(bank, start, end)
+            .map_err(Error::Usage),
+        None => Ok(disassembler.disassemble_rom()),
+    }
+}
+
+fn main() -> ExitCode {
+    env_logger::Builder::from_env(Env::default().default_filter_or("warn")).init();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let Some(args) = parse_args(&args) else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+    match run(args) {
+        Ok(out) => {
+            print!("{out}");
+            ExitCode::SUCCESS
+        },
+        Err(Error::Usage(err)) => {
+            eprintln!("{err}\n{USAGE}");
+            ExitCode::from(2)
+        },
+        Err(Error::Failure(err)) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Option<Args> {
+        parse_args(&list.iter().map(|s| s.to_string()).collect::<Vec<String>>())
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_range("01:4000-4100"), Some((1, 0x4000, 0x4100)));
+        assert_eq!(parse_range("0:0-8000"), Some((0, 0, 0x8000)));
+        assert_eq!(parse_range("01:4000"), None);
+        assert_eq!(parse_range("4000-4100"), None);
+        assert_eq!(parse_range("01:10000-10001"), None);
+    }
+
+    #[test]
+    fn usage_errors() {
+        assert!(args(&[]).is_none());
+        assert!(args(&["a.gb", "b.gb"]).is_none());
+        assert!(args(&["a.gb", "--sym"]).is_none());
+        assert!(args(&["a.gb", "--range", "nope"]).is_none());
+
+        let parsed: Args = args(&["--range", "01:4000-4100", "a.gb", "--sym", "a.sym"]).unwrap();
+        assert_eq!(parsed.filename, "a.gb");
+        assert_eq!(parsed.sym_filename.as_deref(), Some("a.sym"));
+        assert_eq!(parsed.range, Some((1, 0x4000, 0x4100)));
+    }
+
+    #[test]
+    fn ranges_outside_of_the_bank_are_reported() {
+        let path: std::path::PathBuf = env::temp_dir().join(format!("rgbdis-{}.gb", std::process::id()));
+        std::fs::write(&path, vec![0u8; 0x8000]).unwrap();
+        let filename: String = path.to_string_lossy().into_owned();
+        let result: Result<String, Error> = run(Args { sym_filename: None, range: Some((1, 0x2000, 0x8000)), filename: filename.clone() });
+        let whole: Result<String, Error> = run(Args { sym_filename: None, range: Some((1, 0x4000, 0x8000)), filename });
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(Error::Usage(err)) if err.contains("outside of bank 1")));
+        assert!(matches!(whole, Ok(out) if out.starts_with("    nop\n")));
+    }
+}
@@ -1,19 +1,149 @@
 use core::panic;
 use std::env;
 use std::fs::read;
+use std::io::{stdout, Write};
+use std::path::Path;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
 
-use console::Console;
+use cartridge::{Cartridge, CartridgeEvent, WallTimeSource};
+use console::{Button, Console, Model, SCREEN_HEIGHT, SCREEN_WIDTH};
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::PixelFormatEnum;
+
+const SCALE: u32 = 5;
+// 4194304 Hz / 70224 T-cycles per frame
+const FRAME_DURATION: Duration = Duration::from_nanos(16_742_706);
+
+const USAGE: &str = "Usage: rgbe [--boot <boot rom>] [--model <dmg0|dmg|mgb|sgb|sgb2|cgb|agb>] <rom>";
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    assert!(args.len() == 2);
-    let filename: &String = &args[1];
-    
-    let boot_rom: Vec<u8> = read(filename).expect("Failed to read the boot rom");
-    let mut console: Console = match Console::init(boot_rom) {
+    let mut boot_filename: Option<&String> = None;
+    let mut model: Option<Model> = None;
+    let mut filename: Option<&String> = None;
+    let mut it = args.iter().skip(1);
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--boot" => boot_filename = Some(it.next().expect(USAGE)),
+            "--model" => model = Some(Model::from_name(it.next().expect(USAGE)).expect(USAGE)),
+            _ if filename.is_none() => filename = Some(arg),
+            _ => panic!("{USAGE}"),
+        }
+    }
+    let filename: &String = filename.expect(USAGE);
+
+    let boot_rom: Option<Vec<u8>> = boot_filename.map(|f| read(f).expect("Failed to read the boot rom"));
+    let rom: Vec<u8> = read(filename).expect("Failed to read the rom");
+    let mut cartridge: Cartridge = match Cartridge::from_bytes(rom) {
+        Ok(c) => c,
+        Err(err) => panic!("Failed to load cartridge: {err}")
+    };
+    println!("Loaded \"{}\" ({})", cartridge.title(), cartridge.header().cartridge_type.name());
+    let cartridge_title: String = format!("rgbe - {}", cartridge.title());
+    if !cartridge.is_header_checksum_valid() {
+        println!("Warning: header checksum mismatch");
+    }
+
+    // Cartridge clocks follow real time when playing
+    cartridge.set_time_source(Box::new(WallTimeSource::new()));
+    if cartridge.needs_save_file() {
+        let save_path = Cartridge::save_path_for(Path::new(filename));
+        if let Err(err) = cartridge.attach_save_file(save_path) {
+            println!("Warning: failed to load the save file, starting with fresh RAM that won't be saved: {err}");
+        }
+    }
+
+    // CGB titles get a CGB unless told otherwise
+    let model: Model = model.unwrap_or_else(|| Model::for_cartridge(&cartridge));
+    let mut console: Console = match Console::init(cartridge, model, boot_rom) {
         Ok(c) => c,
         Err(msg) => panic!("Fainel to create Console: {msg}")
     };
 
-    console.execute();
+    run(&mut console, cartridge_title);
+}
+
+fn button_for(keycode: Keycode) -> Option<Button> {
+    match keycode {
+        Keycode::Right => Some(Button::Right),
+        Keycode::Left => Some(Button::Left),
+        Keycode::Up => Some(Button::Up),
+        Keycode::Down => Some(Button::Down),
+        Keycode::X => Some(Button::A),
+        Keycode::Z => Some(Button::B),
+        Keycode::Backspace => Some(Button::Select),
+        Keycode::Return => Some(Button::Start),
+        _ => None,
+    }
+}
+
+// The SDL frontend: one emulated frame per displayed frame, paced to the
+// refresh rate of the console.
+fn run(console: &mut Console, title: String) {
+    let sdl = sdl2::init().expect("Failed to initialize SDL");
+    let video = sdl.video().expect("Failed to initialize SDL video");
+    let window = video.window(&title, SCREEN_WIDTH as u32 * SCALE, SCREEN_HEIGHT as u32 * SCALE)
+        .position_centered()
+        .build()
+        .expect("Failed to create the window");
+    let mut canvas = window.into_canvas().build().expect("Failed to create the canvas");
+    let texture_creator = canvas.texture_creator();
+    let mut texture = texture_creator
+        .create_texture_streaming(PixelFormatEnum::RGBA32, SCREEN_WIDTH as u32, SCREEN_HEIGHT as u32)
+        .expect("Failed to create the texture");
+    let mut event_pump = sdl.event_pump().expect("Failed to get the SDL event pump");
+
+    let mut rumble: bool = false;
+    let mut infrared: bool = false;
+    let mut next_frame: Instant = Instant::now();
+    'running: loop {
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. } | Event::KeyDown { keycode: Some(Keycode::Escape), .. } => break 'running,
+                Event::KeyDown { keycode: Some(keycode), repeat: false, .. } => {
+                    if let Some(button) = button_for(keycode) {
+                        console.set_button(button, true);
+                    }
+                },
+                Event::KeyUp { keycode: Some(keycode), .. } => {
+                    if let Some(button) = button_for(keycode) {
+                        console.set_button(button, false);
+                    }
+                },
+                _ => (),
+            }
+        }
+
+        console.run_frame();
+        // Test ROMs report through the link port
+        let serial: Vec<u8> = console.take_serial_output();
+        if !serial.is_empty() {
+            let mut out = stdout().lock();
+            out.write_all(&serial).and_then(|_| out.flush()).expect("Failed to write the serial output");
+        }
+        // No force feedback or IR link here, their state shows in the title instead
+        let (old_rumble, old_infrared): (bool, bool) = (rumble, infrared);
+        while let Some(event) = console.cartridge_mut().poll_event() {
+            match event {
+                CartridgeEvent::Rumble(on) => rumble = on,
+                CartridgeEvent::Infrared(on) => infrared = on,
+            }
+        }
+        if (rumble, infrared) != (old_rumble, old_infrared) {
+            let status: String = format!("{}{}", if rumble { " [rumble]" } else { "" }, if infrared { " [IR]" } else { "" });
+            canvas.window_mut().set_title(&format!("{title}{status}")).expect("Failed to set the window title");
+        }
+        texture.update(None, console.rgba(), SCREEN_WIDTH * 4).expect("Failed to update the texture");
+        canvas.copy(&texture, None, None).expect("Failed to draw the frame");
+        canvas.present();
+
+        next_frame += FRAME_DURATION;
+        match next_frame.checked_duration_since(Instant::now()) {
+            Some(wait) => sleep(wait),
+            // Running behind, don't try to catch up
+            None => next_frame = Instant::now(),
+        }
+    }
 }
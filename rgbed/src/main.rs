@@ -1,16 +1,18 @@
 use core::panic;
-use std::{env, fs, i64};
-use std::fs::{read, File, OpenOptions};
+use std::env;
+use std::fs::{read, OpenOptions};
 use std::collections::HashMap;
+use cartridge::Cartridge;
 use console::debug_addr;
 use console::types::Hookable;
-use constants::reg16::{self, SP};
+use decoder::Instruction;
+use constants::reg16;
 use constants::{flag, reg8};
 use env_logger::Env;
 use text_io::read;
 use std::io::Write;
 
-use console::Console;
+use console::{Console, Model};
 
 mod actions {
     pub const RUN: char             = 'r';
@@ -26,6 +28,7 @@ struct Debugger {
     started: bool,
     stepping: bool,
     verbose: bool,
+    lockup_reported: bool,
     breakpoints: HashMap<u16, String>,
 }
 
@@ -36,11 +39,19 @@ impl Debugger {
             started: false,
             stepping: false,
             verbose: false,
+            lockup_reported: false,
             breakpoints: HashMap::new(),
         }
     }
 
     pub fn run(&mut self, console: &mut Console, addr: u16) {
+        // Drop into the prompt once the CPU hangs, it will not fetch again
+        if let Some(lockup) = console.lockup() && !self.lockup_reported {
+            println!("{lockup}");
+            self.lockup_reported = true;
+            self.stepping = true;
+        }
+
         if self.breakpoints.contains_key(&addr) {
             println!("Breakpoint {} at address 0x{:04X} reached",
                     self.breakpoints.get(&addr).unwrap(), addr);
@@ -94,14 +105,14 @@ impl Debugger {
     fn remove_break(&mut self) {
         let name: String = read!();
         // It is guaranteed that names are unique
-        let mut addr: u16 = std::u16::MAX;
+        let mut addr: u16 = u16::MAX;
         for (key, val) in self.breakpoints.iter() {
             if *val == name {
                 addr = *key;
             }
         };
 
-        if addr == std::u16::MAX {
+        if addr == u16::MAX {
             println!("Breakpoint {name} does not exist");
         } else {
            self.breakpoints.remove(&addr).unwrap();
@@ -126,22 +137,26 @@ impl Debugger {
 }
 
 impl Hookable for Debugger {
-    fn hook(&mut self, console: &mut Console, log: String, addr: u16) {
+    fn hook(&mut self, console: &mut Console, instr: Option<Instruction>, addr: u16) {
         let mut file = OpenOptions::new()
-                .write(true)
                 .append(true)
                 .open("logs.txt")
                 .unwrap();
             
             
-        if addr != std::u16::MAX {
+        if let Some(instr) = instr {
             let msg = format!("A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}\n",
                             console.get_r8(reg8::A), console.get_flags(), console.get_r8(reg8::B), console.get_r8(reg8::C),
                             console.get_r8(reg8::D), console.get_r8(reg8::E), console.get_r8(reg8::H), console.get_r8(reg8::L),
-                            console.get_r16(reg16::SP), addr, console.get_mem(addr.into()), console.get_mem((addr + 1).into()),
-                            console.get_mem((addr + 2).into()), console.get_mem((addr + 3).into()));
+                            console.get_r16(reg16::SP), addr, console.peek_mem(addr.into()), console.peek_mem((addr + 1).into()),
+                            console.peek_mem((addr + 2).into()), console.peek_mem((addr + 3).into()));
             file.write_all(msg.as_bytes()).unwrap();
-            debug_addr(addr, log);
+            // Test ROMs report through the link port
+            let serial: Vec<u8> = console.take_serial_output();
+            if !serial.is_empty() {
+                print!("{}", String::from_utf8_lossy(&serial));
+            }
+            debug_addr(addr, &instr);
             if self.verbose {
                 Debugger::dump_regs(console);
             }
@@ -151,15 +166,37 @@ impl Hookable for Debugger {
     }
 }
 
+const USAGE: &str = "Usage: rgbed [--boot <boot rom>] [--model <dmg0|dmg|mgb|sgb|sgb2|cgb|agb>] <rom>";
+
 // Dissembly ROM:
 // https://www.neviksti.com/DMG/DMG_ROM.asm
 fn main() {
     let args: Vec<String> = env::args().collect();
-    assert!(args.len() == 2);
-    let filename: &String = &args[1];
-    
-    let boot_rom: Vec<u8> = read(filename).expect("Failed to read the boot rom");
-    let mut console: Console = match Console::init(boot_rom) {
+    let mut boot_filename: Option<&String> = None;
+    let mut model: Option<Model> = None;
+    let mut filename: Option<&String> = None;
+    let mut it = args.iter().skip(1);
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--boot" => boot_filename = Some(it.next().expect(USAGE)),
+            "--model" => model = Some(Model::from_name(it.next().expect(USAGE)).expect(USAGE)),
+            _ if filename.is_none() => filename = Some(arg),
+            _ => panic!("{USAGE}"),
+        }
+    }
+    let filename: &String = filename.expect(USAGE);
+
+    let boot_rom: Option<Vec<u8>> = boot_filename.map(|f| read(f).expect("Failed to read the boot rom"));
+    let rom: Vec<u8> = read(filename).expect("Failed to read the rom");
+    let cartridge: Cartridge = match Cartridge::from_bytes(rom) {
+        Ok(c) => c,
+        Err(err) => panic!("Failed to load cartridge: {err}")
+    };
+    println!("Loaded \"{}\" ({})", cartridge.title(), cartridge.header().cartridge_type.name());
+
+    // CGB titles get a CGB unless told otherwise
+    let model: Model = model.unwrap_or_else(|| Model::for_cartridge(&cartridge));
+    let mut console: Console = match Console::init(cartridge, model, boot_rom) {
         Ok(c) => c,
         Err(msg) => panic!("Failed to create Console: {msg}")
     };